@@ -0,0 +1,247 @@
+//! Delays
+
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use super::timer::CountDown;
+use time::Microseconds;
+
+/// A delay that can be polled for completion
+pub trait PollDelay {
+    /// Polls the delay in progress
+    ///
+    /// Returns `Ready` once the delay is over, or if no delay was started. Otherwise returns
+    /// `Pending` and arranges for the task in `cx` to be woken up when the delay is over.
+    fn poll_delay(&mut self, cx: &mut Context) -> Poll<()>;
+}
+
+/// Millisecond delay
+///
+/// This is the `core::future` counterpart of [`blocking::delay::DelayMs`].
+///
+/// [`blocking::delay::DelayMs`]: ../../blocking/delay/trait.DelayMs.html
+pub trait DelayMs<UXX>: PollDelay {
+    /// Starts a delay of `ms` milliseconds, replacing the delay in progress, if any
+    fn start_delay_ms(&mut self, ms: UXX);
+
+    /// Returns a future that resolves after `ms` milliseconds
+    ///
+    /// The delay starts when this method is called, not when the future is first polled.
+    fn delay_ms(&mut self, ms: UXX) -> Delay<'_, Self>
+    where
+        Self: Sized,
+    {
+        self.start_delay_ms(ms);
+        Delay { delay: self }
+    }
+}
+
+/// Microsecond delay
+///
+/// This is the `core::future` counterpart of [`blocking::delay::DelayUs`].
+///
+/// [`blocking::delay::DelayUs`]: ../../blocking/delay/trait.DelayUs.html
+pub trait DelayUs<UXX>: PollDelay {
+    /// Starts a delay of `us` microseconds, replacing the delay in progress, if any
+    fn start_delay_us(&mut self, us: UXX);
+
+    /// Returns a future that resolves after `us` microseconds
+    ///
+    /// The delay starts when this method is called, not when the future is first polled.
+    fn delay_us(&mut self, us: UXX) -> Delay<'_, Self>
+    where
+        Self: Sized,
+    {
+        self.start_delay_us(us);
+        Delay { delay: self }
+    }
+}
+
+/// Future returned by [`DelayMs::delay_ms`] and [`DelayUs::delay_us`]
+///
+/// [`DelayMs::delay_ms`]: trait.DelayMs.html#method.delay_ms
+/// [`DelayUs::delay_us`]: trait.DelayUs.html#method.delay_us
+#[must_use = "futures do nothing unless polled"]
+pub struct Delay<'a, D>
+where
+    D: 'a,
+{
+    delay: &'a mut D,
+}
+
+impl<'a, D> Future for Delay<'a, D>
+where
+    D: PollDelay,
+{
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        self.get_mut().delay.poll_delay(cx)
+    }
+}
+
+/// Delay on top of a `CountDown` timer
+///
+/// This is the `core::future` counterpart of [`blocking::delay::CountDownDelay`]: delays longer
+/// than the longest count down the timer supports are split into several count downs that run back
+/// to back.
+///
+/// [`blocking::delay::CountDownDelay`]: ../../blocking/delay/struct.CountDownDelay.html
+pub struct CountDownDelay<T> {
+    timer: T,
+    max: u32,
+    // microseconds left to count down after the count down in progress
+    remaining: u64,
+    running: bool,
+}
+
+impl<T> CountDownDelay<T>
+where
+    T: CountDown,
+    T::Time: From<Microseconds>,
+{
+    /// Wraps `timer`, which can count down at most `max` in one go
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `max` is zero.
+    pub fn new(timer: T, max: Microseconds) -> Self {
+        assert!(max.0 > 0);
+
+        CountDownDelay {
+            timer,
+            max: max.0,
+            remaining: 0,
+            running: false,
+        }
+    }
+
+    /// Releases the timer
+    pub fn free(self) -> T {
+        self.timer
+    }
+
+    fn start(&mut self, us: u64) {
+        self.remaining = us;
+        self.running = false;
+    }
+}
+
+impl<T> PollDelay for CountDownDelay<T>
+where
+    T: CountDown,
+    T::Time: From<Microseconds>,
+{
+    fn poll_delay(&mut self, cx: &mut Context) -> Poll<()> {
+        loop {
+            if self.running {
+                match self.timer.poll_wait(cx) {
+                    Poll::Ready(()) => self.running = false,
+                    Poll::Pending => return Poll::Pending,
+                }
+            }
+
+            if self.remaining == 0 {
+                return Poll::Ready(());
+            }
+
+            let count = if self.remaining < u64::from(self.max) {
+                self.remaining as u32
+            } else {
+                self.max
+            };
+            self.timer.start(Microseconds(count));
+            self.remaining -= u64::from(count);
+            self.running = true;
+        }
+    }
+}
+
+macro_rules! count_down_delay {
+    ($($ty:ident,)+) => {
+        $(
+            impl<T> DelayUs<$ty> for CountDownDelay<T>
+            where
+                T: CountDown,
+                T::Time: From<Microseconds>,
+            {
+                fn start_delay_us(&mut self, us: $ty) {
+                    self.start(u64::from(us));
+                }
+            }
+
+            impl<T> DelayMs<$ty> for CountDownDelay<T>
+            where
+                T: CountDown,
+                T::Time: From<Microseconds>,
+            {
+                fn start_delay_ms(&mut self, ms: $ty) {
+                    self.start(u64::from(ms) * 1_000);
+                }
+            }
+        )+
+    }
+}
+
+count_down_delay!(u8, u16, u32,);
+
+#[cfg(test)]
+mod tests {
+    use nb;
+    use void::Void;
+
+    use super::super::block_on;
+    use super::super::timer::FromNb;
+    use super::*;
+    use timer;
+
+    /// Timer that records the count downs it was started with and finishes each one after a
+    /// single `WouldBlock`
+    struct Timer<'a> {
+        starts: &'a mut [u32],
+        n: usize,
+        busy: bool,
+    }
+
+    impl<'a> timer::CountDown for Timer<'a> {
+        type Time = Microseconds;
+
+        fn start<C>(&mut self, count: C)
+        where
+            C: Into<Microseconds>,
+        {
+            self.starts[self.n] = count.into().0;
+            self.n += 1;
+            self.busy = true;
+        }
+
+        fn wait(&mut self) -> nb::Result<(), Void> {
+            if self.busy {
+                self.busy = false;
+                Err(nb::Error::WouldBlock)
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    #[test]
+    fn chaining() {
+        let mut starts = [0; 5];
+        {
+            let timer = FromNb::new(Timer {
+                starts: &mut starts,
+                n: 0,
+                busy: false,
+            });
+            let mut delay = CountDownDelay::new(timer, Microseconds(65_535));
+
+            assert_eq!(block_on(delay.delay_ms(200_u8)), ((), 5));
+            assert_eq!(block_on(delay.delay_us(0_u16)), ((), 1));
+            assert_eq!(block_on(delay.delay_us(10_u32)), ((), 2));
+            assert_eq!(delay.free().free().n, 5);
+        }
+        assert_eq!(starts, [65_535, 65_535, 65_535, 3_395, 10]);
+    }
+}
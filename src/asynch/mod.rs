@@ -0,0 +1,41 @@
+//! `core::future` based counterparts of the `nb` traits
+//!
+//! The traits in this module are *polled*, like `core::future::Future`, and each provides a
+//! method that returns a concrete future, so they can be used with `.await` on any executor.
+//! HAL implementations signal completion by waking the task, typically from an interrupt handler.
+//!
+//! Implementations of the `nb` traits can be converted with the adapters provided here; those
+//! wake the task right away whenever they return `Pending`, so the executor busy-polls them.
+//!
+//! *This module is available if embedded-hal is built with the `"unproven"` feature.*
+
+pub mod delay;
+pub mod timer;
+
+#[cfg(test)]
+fn block_on<F>(mut future: F) -> (F::Output, usize)
+where
+    F: ::core::future::Future + Unpin,
+{
+    use core::pin::Pin;
+    use core::ptr;
+    use core::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+    fn clone(_: *const ()) -> RawWaker {
+        RawWaker::new(ptr::null(), &VTABLE)
+    }
+    fn noop(_: *const ()) {}
+    static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+
+    let waker = unsafe { Waker::from_raw(RawWaker::new(ptr::null(), &VTABLE)) };
+    let mut cx = Context::from_waker(&waker);
+
+    // returns the number of times the future was polled along with its output
+    let mut polls = 0;
+    loop {
+        polls += 1;
+        if let Poll::Ready(output) = Pin::new(&mut future).poll(&mut cx) {
+            return (output, polls);
+        }
+    }
+}
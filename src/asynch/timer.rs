@@ -0,0 +1,146 @@
+//! Timers
+
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use nb;
+
+use timer;
+
+/// A count down timer
+///
+/// This is the `core::future` counterpart of [`timer::CountDown`]; see that trait for the
+/// contract.
+///
+/// [`timer::CountDown`]: ../../timer/trait.CountDown.html
+pub trait CountDown {
+    /// The unit of time used by this timer
+    type Time;
+
+    /// Starts a new count down
+    fn start<T>(&mut self, count: T)
+    where
+        T: Into<Self::Time>;
+
+    /// Polls the count down
+    ///
+    /// Returns `Ready` once the count down finishes. Otherwise returns `Pending` and arranges for
+    /// the task in `cx` to be woken up when it finishes.
+    fn poll_wait(&mut self, cx: &mut Context) -> Poll<()>;
+
+    /// Returns a future that resolves once the count down finishes
+    fn wait(&mut self) -> Wait<'_, Self>
+    where
+        Self: Sized,
+    {
+        Wait { timer: self }
+    }
+}
+
+/// Future returned by [`CountDown::wait`](trait.CountDown.html#method.wait)
+#[must_use = "futures do nothing unless polled"]
+pub struct Wait<'a, T>
+where
+    T: 'a,
+{
+    timer: &'a mut T,
+}
+
+impl<'a, T> Future for Wait<'a, T>
+where
+    T: CountDown,
+{
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        self.get_mut().timer.poll_wait(cx)
+    }
+}
+
+/// Adapter that turns an `nb` based `timer::CountDown` into a `CountDown`
+///
+/// The task is woken up right away every time the count down is still in progress.
+pub struct FromNb<T> {
+    timer: T,
+}
+
+impl<T> FromNb<T>
+where
+    T: timer::CountDown,
+{
+    /// Wraps `timer`
+    pub fn new(timer: T) -> Self {
+        FromNb { timer }
+    }
+
+    /// Releases the wrapped timer
+    pub fn free(self) -> T {
+        self.timer
+    }
+}
+
+impl<T> CountDown for FromNb<T>
+where
+    T: timer::CountDown,
+{
+    type Time = T::Time;
+
+    fn start<C>(&mut self, count: C)
+    where
+        C: Into<T::Time>,
+    {
+        self.timer.start(count)
+    }
+
+    fn poll_wait(&mut self, cx: &mut Context) -> Poll<()> {
+        match self.timer.wait() {
+            Ok(()) => Poll::Ready(()),
+            Err(nb::Error::WouldBlock) => {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+            Err(nb::Error::Other(e)) => match e {},
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use void::Void;
+
+    use super::super::block_on;
+    use super::*;
+
+    /// Timer that finishes after `count` polls
+    struct Timer(u32);
+
+    impl timer::CountDown for Timer {
+        type Time = u32;
+
+        fn start<C>(&mut self, count: C)
+        where
+            C: Into<u32>,
+        {
+            self.0 = count.into();
+        }
+
+        fn wait(&mut self) -> nb::Result<(), Void> {
+            if self.0 == 0 {
+                Ok(())
+            } else {
+                self.0 -= 1;
+                Err(nb::Error::WouldBlock)
+            }
+        }
+    }
+
+    #[test]
+    fn from_nb() {
+        let mut timer = FromNb::new(Timer(0));
+
+        timer.start(3_u32);
+        assert_eq!(block_on(timer.wait()), ((), 4));
+        assert_eq!(timer.free().0, 0);
+    }
+}
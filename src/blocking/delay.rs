@@ -5,7 +5,8 @@
 //! The `Timer` trait provides a *non-blocking* timer abstraction and it's meant to be used to build
 //! higher level abstractions like I/O operations with timeouts. OTOH, these delays traits only
 //! provide *blocking* functionality. Note that you can also use the `timer::CountDown` trait to
-//! implement blocking delays.
+//! implement blocking delays; `CountDownDelay` does that for any timer whose `Time` can be created
+//! from `time::Microseconds`.
 
 /// Millisecond delay
 ///
@@ -24,3 +25,218 @@ pub trait DelayUs<UXX> {
     /// Pauses execution for `us` microseconds
     fn delay_us(&mut self, us: UXX);
 }
+
+#[cfg(feature = "unproven")]
+use time::Microseconds;
+#[cfg(feature = "unproven")]
+use timer::CountDown;
+
+/// Blocking delay on top of a `CountDown` timer
+///
+/// Delays longer than the longest count down the timer supports are split into several count
+/// downs that run back to back.
+///
+/// *This struct is available if embedded-hal is built with the `"unproven"` feature.*
+///
+/// # Examples
+///
+/// ```
+/// extern crate embedded_hal as hal;
+///
+/// use hal::blocking::delay::{CountDownDelay, DelayMs};
+/// use hal::time::Microseconds;
+///
+/// fn main() {
+///     let timer: Timer6 = {
+///         // ..
+/// #       Timer6
+///     };
+///
+///     // a 16-bit timer running at 1 MHz overflows after 65.535 ms
+///     let mut delay = CountDownDelay::new(timer, Microseconds(65_535));
+///
+///     // runs three count downs of 65.535 ms and a fourth one of 3.395 ms
+///     delay.delay_ms(200_u8);
+/// }
+///
+/// # extern crate nb;
+/// # extern crate void;
+/// # struct Ticks(u16);
+/// # impl From<Microseconds> for Ticks {
+/// #     fn from(us: Microseconds) -> Ticks { Ticks(us.0 as u16) }
+/// # }
+/// # struct Timer6;
+/// # impl hal::timer::CountDown for Timer6 {
+/// #     type Time = Ticks;
+/// #     fn start<T>(&mut self, _: T) where T: Into<Ticks> {}
+/// #     fn wait(&mut self) -> nb::Result<(), void::Void> { Ok(()) }
+/// # }
+/// ```
+#[cfg(feature = "unproven")]
+pub struct CountDownDelay<T> {
+    timer: T,
+    max: u32,
+}
+
+#[cfg(feature = "unproven")]
+impl<T> CountDownDelay<T>
+where
+    T: CountDown,
+    T::Time: From<Microseconds>,
+{
+    /// Wraps `timer`, which can count down at most `max` in one go
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `max` is zero.
+    pub fn new(timer: T, max: Microseconds) -> Self {
+        assert!(max.0 > 0);
+
+        CountDownDelay { timer, max: max.0 }
+    }
+
+    /// Releases the timer
+    pub fn free(self) -> T {
+        self.timer
+    }
+
+    fn delay(&mut self, mut us: u64) {
+        while us > 0 {
+            let count = if us < u64::from(self.max) {
+                us as u32
+            } else {
+                self.max
+            };
+
+            self.timer.start(Microseconds(count));
+            block!(self.timer.wait()).unwrap_or_else(|e| match e {});
+            us -= u64::from(count);
+        }
+    }
+}
+
+#[cfg(feature = "unproven")]
+impl<T> DelayUs<u32> for CountDownDelay<T>
+where
+    T: CountDown,
+    T::Time: From<Microseconds>,
+{
+    fn delay_us(&mut self, us: u32) {
+        self.delay(u64::from(us));
+    }
+}
+
+#[cfg(feature = "unproven")]
+impl<T> DelayMs<u32> for CountDownDelay<T>
+where
+    T: CountDown,
+    T::Time: From<Microseconds>,
+{
+    fn delay_ms(&mut self, ms: u32) {
+        self.delay(u64::from(ms) * 1_000);
+    }
+}
+
+macro_rules! count_down_delay {
+    ($($ty:ident,)+) => {
+        $(
+            #[cfg(feature = "unproven")]
+            impl<T> DelayUs<$ty> for CountDownDelay<T>
+            where
+                T: CountDown,
+                T::Time: From<Microseconds>,
+            {
+                fn delay_us(&mut self, us: $ty) {
+                    self.delay_us(u32::from(us));
+                }
+            }
+
+            #[cfg(feature = "unproven")]
+            impl<T> DelayMs<$ty> for CountDownDelay<T>
+            where
+                T: CountDown,
+                T::Time: From<Microseconds>,
+            {
+                fn delay_ms(&mut self, ms: $ty) {
+                    self.delay_ms(u32::from(ms));
+                }
+            }
+        )+
+    }
+}
+
+count_down_delay!(u8, u16,);
+
+#[cfg(all(test, feature = "unproven"))]
+mod tests {
+    use super::*;
+
+    use nb;
+    use void::Void;
+
+    /// Timer that records the count downs it was started with
+    struct Timer<'a> {
+        starts: &'a mut [u32],
+        n: usize,
+        polls: usize,
+    }
+
+    impl<'a> CountDown for Timer<'a> {
+        type Time = Microseconds;
+
+        fn start<C>(&mut self, count: C)
+        where
+            C: Into<Microseconds>,
+        {
+            self.starts[self.n] = count.into().0;
+            self.n += 1;
+        }
+
+        fn wait(&mut self) -> nb::Result<(), Void> {
+            self.polls += 1;
+            if self.polls == 3 {
+                self.polls = 0;
+                Ok(())
+            } else {
+                Err(nb::Error::WouldBlock)
+            }
+        }
+    }
+
+    #[test]
+    fn chaining() {
+        let mut starts = [0; 5];
+        {
+            let timer = Timer {
+                starts: &mut starts,
+                n: 0,
+                polls: 0,
+            };
+            let mut delay = CountDownDelay::new(timer, Microseconds(65_535));
+
+            delay.delay_ms(200_u8);
+            delay.delay_us(0_u16);
+            delay.delay_us(10_u32);
+
+            let timer = delay.free();
+            assert_eq!(timer.n, 5);
+        }
+        assert_eq!(starts, [65_535, 65_535, 65_535, 3_395, 10]);
+    }
+
+    #[test]
+    fn long_delay() {
+        let mut starts = [0; 4];
+        let timer = Timer {
+            starts: &mut starts,
+            n: 0,
+            polls: 0,
+        };
+        let mut delay = CountDownDelay::new(timer, Microseconds(u32::MAX));
+
+        // longer than `u32::MAX` microseconds
+        delay.delay_ms(5_000_000_u32);
+        assert_eq!(delay.free().n, 2);
+        assert_eq!(starts[..2], [u32::MAX, 705_032_705]);
+    }
+}
@@ -5,6 +5,80 @@
 //! Operations on 10-bit slave addresses are not supported by the API yet (but applications might
 //! be able to emulate some operations).
 
+#[cfg(feature = "unproven")]
+use void::Void;
+
+#[cfg(feature = "unproven")]
+pub mod recover;
+#[cfg(feature = "unproven")]
+pub mod retry;
+#[cfg(feature = "unproven")]
+pub mod scan;
+#[cfg(feature = "unproven")]
+pub mod shared;
+
+/// I2C error kind
+///
+/// This represents a common set of I2C operation errors. HAL implementations are free to define
+/// more specific or additional error types. However, by providing a mapping to these common I2C
+/// errors, generic code can still react to them.
+///
+/// *This enumeration is available if embedded-hal is built with the `"unproven"` feature.*
+#[cfg(feature = "unproven")]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ErrorKind {
+    /// Bus error occurred, e.g. a misplaced START or STOP condition was detected
+    Bus,
+    /// The arbitration was lost, e.g. another master started a transfer at the same time
+    ArbitrationLoss,
+    /// A bus operation was not acknowledged
+    NoAcknowledge(NoAcknowledgeSource),
+    /// The peripheral receive buffer was overrun
+    Overrun,
+    /// A different error occurred. The original error may contain more information.
+    Other,
+}
+
+/// I2C no acknowledge error source
+///
+/// *This enumeration is available if embedded-hal is built with the `"unproven"` feature.*
+#[cfg(feature = "unproven")]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum NoAcknowledgeSource {
+    /// The device did not acknowledge its address, e.g. because it's not on the bus or busy
+    Address,
+    /// The device did not acknowledge a data byte
+    Data,
+    /// The HAL can't tell whether the address or a data byte was not acknowledged
+    Unknown,
+}
+
+/// I2C error
+///
+/// Implement this trait for the `Error` type of your I2C implementation to let generic code find
+/// out what went wrong.
+///
+/// *This trait is available if embedded-hal is built with the `"unproven"` feature.*
+#[cfg(feature = "unproven")]
+pub trait Error {
+    /// Converts this error into a generic error kind
+    fn kind(&self) -> ErrorKind;
+}
+
+#[cfg(feature = "unproven")]
+impl Error for ErrorKind {
+    fn kind(&self) -> ErrorKind {
+        *self
+    }
+}
+
+#[cfg(feature = "unproven")]
+impl Error for Void {
+    fn kind(&self) -> ErrorKind {
+        match *self {}
+    }
+}
+
 /// Blocking read
 pub trait Read {
     /// Error type
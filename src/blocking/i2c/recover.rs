@@ -0,0 +1,267 @@
+//! I2C bus recovery
+//!
+//! A slave that gets reset (or loses track of the clock) in the middle of a read transfer may keep
+//! driving SDA low while it waits for clock pulses that will never come. In that state every
+//! transfer fails because the master can't generate a START condition.
+//!
+//! The usual way out, described in section 3.1.16 of the I2C specification (UM10204), is to clock
+//! SCL until the slave releases SDA (at most nine pulses), then generate a STOP condition.
+//!
+//! *This module is available if embedded-hal is built with the `"unproven"` feature.*
+
+use blocking::delay::DelayUs;
+use digital::v2::{InputPin, OutputPin};
+
+/// Maximum number of clock pulses needed to free the bus
+///
+/// Eight data bits plus the acknowledge bit.
+pub const MAX_CLOCK_PULSES: u8 = 9;
+
+/// Bus recovery
+///
+/// HALs whose I2C peripheral can perform the recovery sequence in hardware (or that can
+/// temporarily switch the SCL and SDA pins to GPIO mode) should implement this trait.
+pub trait Recover {
+    /// Error type
+    type Error;
+
+    /// Tries to free a bus that's being held by a slave
+    ///
+    /// Clocks SCL up to [`MAX_CLOCK_PULSES`] times until SDA is released and then generates a STOP
+    /// condition.
+    ///
+    /// Returns `Ok(true)` if both SCL and SDA are released (high) after the procedure, and
+    /// `Ok(false)` if the bus is still stuck, e.g. because a device is holding SCL low.
+    ///
+    /// [`MAX_CLOCK_PULSES`]: constant.MAX_CLOCK_PULSES.html
+    fn recover(&mut self) -> Result<bool, Self::Error>;
+}
+
+/// Bus recovery using GPIO access to the bus lines
+///
+/// Both pins must be configured as open drain outputs whose input level can be read back: calling
+/// `set_high` releases the line and `set_low` pulls it low.
+///
+/// The I2C peripheral must not be driving the pins while the recovery procedure runs.
+pub struct GpioRecovery<SCL, SDA, D> {
+    scl: SCL,
+    sda: SDA,
+    delay: D,
+    half_period_us: u8,
+}
+
+impl<SCL, SDA, D, E> GpioRecovery<SCL, SDA, D>
+where
+    SCL: OutputPin<Error = E> + InputPin<Error = E>,
+    SDA: OutputPin<Error = E> + InputPin<Error = E>,
+    D: DelayUs<u8>,
+{
+    /// Creates a new recovery helper that clocks SCL at 100 kHz
+    pub fn new(scl: SCL, sda: SDA, delay: D) -> Self {
+        GpioRecovery {
+            scl,
+            sda,
+            delay,
+            half_period_us: 5,
+        }
+    }
+
+    /// Changes the time SCL is held low and high during each clock pulse
+    pub fn set_half_period_us(&mut self, half_period_us: u8) {
+        self.half_period_us = half_period_us;
+    }
+
+    /// Releases the pins and the delay provider
+    pub fn free(self) -> (SCL, SDA, D) {
+        (self.scl, self.sda, self.delay)
+    }
+
+    /// Returns `true` if either bus line is being held low
+    ///
+    /// Only meaningful while the bus is idle, i.e. outside of a transfer.
+    pub fn is_stuck(&self) -> Result<bool, E> {
+        Ok(self.scl.is_low()? || self.sda.is_low()?)
+    }
+
+    fn wait(&mut self) {
+        let half_period_us = self.half_period_us;
+        self.delay.delay_us(half_period_us);
+    }
+}
+
+impl<SCL, SDA, D, E> Recover for GpioRecovery<SCL, SDA, D>
+where
+    SCL: OutputPin<Error = E> + InputPin<Error = E>,
+    SDA: OutputPin<Error = E> + InputPin<Error = E>,
+    D: DelayUs<u8>,
+{
+    type Error = E;
+
+    fn recover(&mut self) -> Result<bool, E> {
+        self.sda.set_high()?;
+        self.scl.set_high()?;
+        self.wait();
+
+        // clocking can't help if someone else is holding the clock line
+        if self.scl.is_low()? {
+            return Ok(false);
+        }
+
+        for _ in 0..MAX_CLOCK_PULSES {
+            if self.sda.is_high()? {
+                break;
+            }
+
+            self.scl.set_low()?;
+            self.wait();
+            self.scl.set_high()?;
+            self.wait();
+        }
+
+        // STOP condition: SDA goes high while SCL is high
+        self.scl.set_low()?;
+        self.wait();
+        self.sda.set_low()?;
+        self.wait();
+        self.scl.set_high()?;
+        self.wait();
+        self.sda.set_high()?;
+        self.wait();
+
+        Ok(!self.is_stuck()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::cell::Cell;
+
+    /// Open drain bus with a slave that holds SDA low for a number of clock pulses
+    struct Bus {
+        scl_low: Cell<bool>,
+        sda_low: Cell<bool>,
+        scl_stuck: Cell<bool>,
+        slave_pulses: Cell<u8>,
+        pulses: Cell<u8>,
+    }
+
+    impl Bus {
+        fn new(slave_pulses: u8) -> Self {
+            Bus {
+                scl_low: Cell::new(false),
+                sda_low: Cell::new(false),
+                scl_stuck: Cell::new(false),
+                slave_pulses: Cell::new(slave_pulses),
+                pulses: Cell::new(0),
+            }
+        }
+    }
+
+    struct Scl<'a>(&'a Bus);
+    struct Sda<'a>(&'a Bus);
+    struct NoDelay;
+
+    impl<'a> OutputPin for Scl<'a> {
+        type Error = ();
+
+        fn set_low(&mut self) -> Result<(), ()> {
+            self.0.scl_low.set(true);
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), ()> {
+            if self.0.scl_low.get() {
+                self.0.pulses.set(self.0.pulses.get() + 1);
+                let remaining = self.0.slave_pulses.get();
+                self.0.slave_pulses.set(remaining.saturating_sub(1));
+            }
+            self.0.scl_low.set(false);
+            Ok(())
+        }
+    }
+
+    impl<'a> InputPin for Scl<'a> {
+        type Error = ();
+
+        fn is_high(&self) -> Result<bool, ()> {
+            self.is_low().map(|low| !low)
+        }
+
+        fn is_low(&self) -> Result<bool, ()> {
+            Ok(self.0.scl_low.get() || self.0.scl_stuck.get())
+        }
+    }
+
+    impl<'a> OutputPin for Sda<'a> {
+        type Error = ();
+
+        fn set_low(&mut self) -> Result<(), ()> {
+            self.0.sda_low.set(true);
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), ()> {
+            self.0.sda_low.set(false);
+            Ok(())
+        }
+    }
+
+    impl<'a> InputPin for Sda<'a> {
+        type Error = ();
+
+        fn is_high(&self) -> Result<bool, ()> {
+            self.is_low().map(|low| !low)
+        }
+
+        fn is_low(&self) -> Result<bool, ()> {
+            Ok(self.0.sda_low.get() || self.0.slave_pulses.get() > 0)
+        }
+    }
+
+    impl DelayUs<u8> for NoDelay {
+        fn delay_us(&mut self, _: u8) {}
+    }
+
+    #[test]
+    fn idle_bus() {
+        let bus = Bus::new(0);
+        let mut recovery = GpioRecovery::new(Scl(&bus), Sda(&bus), NoDelay);
+
+        assert_eq!(recovery.is_stuck(), Ok(false));
+        assert_eq!(recovery.recover(), Ok(true));
+        // only the clock pulse of the STOP condition
+        assert_eq!(bus.pulses.get(), 1);
+    }
+
+    #[test]
+    fn frees_sda() {
+        let bus = Bus::new(3);
+        let mut recovery = GpioRecovery::new(Scl(&bus), Sda(&bus), NoDelay);
+
+        assert_eq!(recovery.is_stuck(), Ok(true));
+        assert_eq!(recovery.recover(), Ok(true));
+        assert_eq!(bus.pulses.get(), 3 + 1);
+        assert_eq!(recovery.is_stuck(), Ok(false));
+    }
+
+    #[test]
+    fn gives_up_after_nine_pulses() {
+        let bus = Bus::new(20);
+        let mut recovery = GpioRecovery::new(Scl(&bus), Sda(&bus), NoDelay);
+
+        assert_eq!(recovery.recover(), Ok(false));
+        assert_eq!(bus.pulses.get(), MAX_CLOCK_PULSES + 1);
+    }
+
+    #[test]
+    fn scl_held_low() {
+        let bus = Bus::new(0);
+        bus.scl_stuck.set(true);
+        let mut recovery = GpioRecovery::new(Scl(&bus), Sda(&bus), NoDelay);
+
+        assert_eq!(recovery.recover(), Ok(false));
+        assert_eq!(bus.pulses.get(), 0);
+    }
+}
@@ -0,0 +1,162 @@
+//! Retrying transfers that were not acknowledged
+//!
+//! Some devices stop acknowledging their address while they are busy, e.g. EEPROMs during an
+//! internal write cycle. The usual way to wait for them is to retry the transfer until the address
+//! is acknowledged again ("acknowledge polling").
+//!
+//! *This module is available if embedded-hal is built with the `"unproven"` feature.*
+
+use super::{Error, ErrorKind, NoAcknowledgeSource, Read, Write, WriteRead};
+
+/// Wrapper that retries transfers whose address was not acknowledged
+///
+/// Only `NoAcknowledge(Address)` errors are retried: at that point no data has reached the device
+/// yet so the transfer can safely be repeated. All other errors are returned right away.
+pub struct RetryOnNack<I2C> {
+    i2c: I2C,
+    attempts: u16,
+}
+
+impl<I2C> RetryOnNack<I2C> {
+    /// Wraps `i2c`, trying each transfer at most `attempts` times
+    ///
+    /// An `attempts` value of zero is treated as one.
+    pub fn new(i2c: I2C, attempts: u16) -> Self {
+        RetryOnNack {
+            i2c,
+            attempts: if attempts == 0 { 1 } else { attempts },
+        }
+    }
+
+    /// Releases the wrapped I2C bus
+    pub fn free(self) -> I2C {
+        self.i2c
+    }
+
+    fn retry<F, E>(&mut self, mut f: F) -> Result<(), E>
+    where
+        F: FnMut(&mut I2C) -> Result<(), E>,
+        E: Error,
+    {
+        let mut attempt = 1;
+        loop {
+            match f(&mut self.i2c) {
+                Err(ref e)
+                    if attempt < self.attempts
+                        && e.kind() == ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address) =>
+                {
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl<I2C> Read for RetryOnNack<I2C>
+where
+    I2C: Read,
+    I2C::Error: Error,
+{
+    type Error = I2C::Error;
+
+    fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        self.retry(|i2c| i2c.read(address, buffer))
+    }
+}
+
+impl<I2C> Write for RetryOnNack<I2C>
+where
+    I2C: Write,
+    I2C::Error: Error,
+{
+    type Error = I2C::Error;
+
+    fn write(&mut self, addr: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.retry(|i2c| i2c.write(addr, bytes))
+    }
+}
+
+impl<I2C> WriteRead for RetryOnNack<I2C>
+where
+    I2C: WriteRead,
+    I2C::Error: Error,
+{
+    type Error = I2C::Error;
+
+    fn write_read(
+        &mut self,
+        address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        self.retry(|i2c| i2c.write_read(address, bytes, buffer))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fails the first `failures` transfers with `error`
+    struct FlakyBus {
+        failures: u16,
+        error: ErrorKind,
+        transfers: u16,
+    }
+
+    impl Write for FlakyBus {
+        type Error = ErrorKind;
+
+        fn write(&mut self, _: u8, _: &[u8]) -> Result<(), ErrorKind> {
+            self.transfers += 1;
+            if self.transfers <= self.failures {
+                Err(self.error)
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    const ADDRESS_NACK: ErrorKind = ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address);
+
+    #[test]
+    fn retries_address_nack() {
+        let bus = FlakyBus {
+            failures: 3,
+            error: ADDRESS_NACK,
+            transfers: 0,
+        };
+        let mut i2c = RetryOnNack::new(bus, 4);
+
+        assert_eq!(i2c.write(0x50, &[0]), Ok(()));
+        assert_eq!(i2c.free().transfers, 4);
+    }
+
+    #[test]
+    fn gives_up() {
+        let bus = FlakyBus {
+            failures: 5,
+            error: ADDRESS_NACK,
+            transfers: 0,
+        };
+        let mut i2c = RetryOnNack::new(bus, 3);
+
+        assert_eq!(i2c.write(0x50, &[0]), Err(ADDRESS_NACK));
+        assert_eq!(i2c.free().transfers, 3);
+    }
+
+    #[test]
+    fn other_errors_are_not_retried() {
+        let data_nack = ErrorKind::NoAcknowledge(NoAcknowledgeSource::Data);
+        let bus = FlakyBus {
+            failures: 1,
+            error: data_nack,
+            transfers: 0,
+        };
+        let mut i2c = RetryOnNack::new(bus, 3);
+
+        assert_eq!(i2c.write(0x50, &[0]), Err(data_nack));
+        assert_eq!(i2c.free().transfers, 1);
+    }
+}
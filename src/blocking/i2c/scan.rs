@@ -0,0 +1,332 @@
+//! I2C bus scanning
+//!
+//! Finds out which 7-bit addresses are acknowledged by a device. The reserved addresses `0x00` -
+//! `0x07` and `0x78` - `0x7F` are never probed.
+//!
+//! *This module is available if embedded-hal is built with the `"unproven"` feature.*
+//!
+//! ```
+//! extern crate embedded_hal as hal;
+//!
+//! use hal::blocking::i2c::scan::{self, Probe};
+//! # use hal::blocking::i2c::{ErrorKind, NoAcknowledgeSource, Read, Write};
+//!
+//! # struct I2c1;
+//! # impl Read for I2c1 {
+//! #     type Error = ErrorKind;
+//! #     fn read(&mut self, address: u8, _: &mut [u8]) -> Result<(), ErrorKind> {
+//! #         if address == 0x48 { Ok(()) }
+//! #         else { Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address)) }
+//! #     }
+//! # }
+//! # impl Write for I2c1 {
+//! #     type Error = ErrorKind;
+//! #     fn write(&mut self, address: u8, _: &[u8]) -> Result<(), ErrorKind> {
+//! #         self.read(address, &mut [])
+//! #     }
+//! # }
+//! fn main() {
+//!     let mut i2c: I2c1 = {
+//!         // ..
+//! #       I2c1
+//!     };
+//!
+//!     let devices = scan::scan(&mut i2c, Probe::OneByteRead).unwrap();
+//!     for address in devices.iter() {
+//!         // prints "found device at 0x48"
+//!         println!("found device at {:#04x}", address);
+//!     }
+//! }
+//! ```
+
+use super::{Error, ErrorKind, Read, Write};
+
+/// Lowest address that's probed
+pub const FIRST_ADDRESS: u8 = 0x08;
+
+/// Highest address that's probed
+pub const LAST_ADDRESS: u8 = 0x77;
+
+/// Returns `true` if `address` is reserved by the I2C specification
+///
+/// This covers the general call address, START byte, CBUS, high speed mode master codes and
+/// 10-bit addressing prefixes.
+pub fn is_reserved(address: u8) -> bool {
+    !(FIRST_ADDRESS..=LAST_ADDRESS).contains(&address)
+}
+
+/// How devices are probed
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Probe {
+    /// Write zero bytes, i.e. only send the address followed by a STOP condition
+    ///
+    /// This is the fastest method but not every I2C peripheral supports empty writes and some
+    /// devices misbehave when their address is followed by a STOP.
+    ZeroLengthWrite,
+    /// Read a single byte
+    ///
+    /// Safe for most devices, but beware that reading may have side effects like clearing
+    /// interrupt flags.
+    OneByteRead,
+}
+
+/// Iterator over the addresses of the devices that are on the bus
+///
+/// Each address is probed lazily, when the iterator advances. Addresses that are not acknowledged
+/// are skipped; any other error is yielded and scanning continues with the next address.
+pub struct Scanner<'a, I2C>
+where
+    I2C: 'a,
+{
+    i2c: &'a mut I2C,
+    probe: Probe,
+    next: u8,
+}
+
+impl<'a, I2C, E> Scanner<'a, I2C>
+where
+    I2C: Read<Error = E> + Write<Error = E>,
+    E: Error,
+{
+    /// Creates a scanner that probes all non reserved addresses
+    pub fn new(i2c: &'a mut I2C, probe: Probe) -> Self {
+        Scanner {
+            i2c,
+            probe,
+            next: FIRST_ADDRESS,
+        }
+    }
+
+    fn probe(&mut self, address: u8) -> Result<bool, E> {
+        let result = match self.probe {
+            Probe::ZeroLengthWrite => self.i2c.write(address, &[]),
+            Probe::OneByteRead => self.i2c.read(address, &mut [0]),
+        };
+
+        match result {
+            Ok(()) => Ok(true),
+            Err(e) => match e.kind() {
+                ErrorKind::NoAcknowledge(_) => Ok(false),
+                _ => Err(e),
+            },
+        }
+    }
+}
+
+impl<'a, I2C, E> Iterator for Scanner<'a, I2C>
+where
+    I2C: Read<Error = E> + Write<Error = E>,
+    E: Error,
+{
+    type Item = Result<u8, E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.next <= LAST_ADDRESS {
+            let address = self.next;
+            self.next += 1;
+
+            match self.probe(address) {
+                Ok(true) => return Some(Ok(address)),
+                Ok(false) => {}
+                Err(e) => return Some(Err(e)),
+            }
+        }
+
+        None
+    }
+}
+
+/// Set of 7-bit addresses
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct AddressSet {
+    bits: [u32; 4],
+}
+
+impl AddressSet {
+    /// Creates an empty set
+    pub fn new() -> Self {
+        AddressSet::default()
+    }
+
+    /// Adds `address` to the set
+    ///
+    /// # Panics
+    ///
+    /// If `address` doesn't fit in 7 bits
+    pub fn insert(&mut self, address: u8) {
+        assert!(address < 0x80);
+        self.bits[usize::from(address / 32)] |= 1 << (address % 32);
+    }
+
+    /// Returns `true` if `address` is in the set
+    pub fn contains(&self, address: u8) -> bool {
+        address < 0x80 && self.bits[usize::from(address / 32)] & (1 << (address % 32)) != 0
+    }
+
+    /// Returns the number of addresses in the set
+    pub fn len(&self) -> usize {
+        self.bits
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    /// Returns `true` if the set is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns an iterator over the addresses in the set, in ascending order
+    pub fn iter(&self) -> Addresses {
+        Addresses {
+            set: *self,
+            next: 0,
+        }
+    }
+}
+
+/// Iterator over the addresses in an [`AddressSet`](struct.AddressSet.html)
+pub struct Addresses {
+    set: AddressSet,
+    next: u8,
+}
+
+impl Iterator for Addresses {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        while self.next < 0x80 {
+            let address = self.next;
+            self.next += 1;
+
+            if self.set.contains(address) {
+                return Some(address);
+            }
+        }
+
+        None
+    }
+}
+
+/// Probes every non reserved address and returns the set of addresses that were acknowledged
+///
+/// Stops at the first error that's not a `NoAcknowledge` error.
+pub fn scan<I2C, E>(i2c: &mut I2C, probe: Probe) -> Result<AddressSet, E>
+where
+    I2C: Read<Error = E> + Write<Error = E>,
+    E: Error,
+{
+    let mut devices = AddressSet::new();
+    for address in Scanner::new(i2c, probe) {
+        devices.insert(address?);
+    }
+
+    Ok(devices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use blocking::i2c::NoAcknowledgeSource;
+
+    struct Bus {
+        devices: AddressSet,
+        broken: Option<u8>,
+        probed: AddressSet,
+        reads: usize,
+        writes: usize,
+    }
+
+    impl Bus {
+        fn new(devices: &[u8]) -> Self {
+            let mut set = AddressSet::new();
+            for &address in devices {
+                set.insert(address);
+            }
+
+            Bus {
+                devices: set,
+                broken: None,
+                probed: AddressSet::new(),
+                reads: 0,
+                writes: 0,
+            }
+        }
+
+        fn transfer(&mut self, address: u8) -> Result<(), ErrorKind> {
+            self.probed.insert(address);
+            if self.broken == Some(address) {
+                Err(ErrorKind::ArbitrationLoss)
+            } else if self.devices.contains(address) {
+                Ok(())
+            } else {
+                Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address))
+            }
+        }
+    }
+
+    impl Read for Bus {
+        type Error = ErrorKind;
+
+        fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), ErrorKind> {
+            assert_eq!(buffer.len(), 1);
+            self.reads += 1;
+            self.transfer(address)
+        }
+    }
+
+    impl Write for Bus {
+        type Error = ErrorKind;
+
+        fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), ErrorKind> {
+            assert!(bytes.is_empty());
+            self.writes += 1;
+            self.transfer(address)
+        }
+    }
+
+    #[test]
+    fn finds_devices() {
+        let mut bus = Bus::new(&[0x00, 0x08, 0x48, 0x77, 0x78]);
+
+        let devices = scan(&mut bus, Probe::ZeroLengthWrite).unwrap();
+
+        let mut found = devices.iter();
+        assert_eq!(found.next(), Some(0x08));
+        assert_eq!(found.next(), Some(0x48));
+        assert_eq!(found.next(), Some(0x77));
+        assert_eq!(found.next(), None);
+        assert_eq!(devices.len(), 3);
+        assert_eq!(bus.writes, 112);
+        assert_eq!(bus.reads, 0);
+    }
+
+    #[test]
+    fn skips_reserved_addresses() {
+        let mut bus = Bus::new(&[]);
+
+        assert!(scan(&mut bus, Probe::OneByteRead).unwrap().is_empty());
+        assert_eq!(bus.reads, 112);
+        assert!((0..0x80).all(|address| bus.probed.contains(address) != is_reserved(address)));
+    }
+
+    #[test]
+    fn yields_errors_and_continues() {
+        let mut bus = Bus::new(&[0x20, 0x40]);
+        bus.broken = Some(0x30);
+
+        {
+            let mut scanner = Scanner::new(&mut bus, Probe::OneByteRead);
+            assert_eq!(scanner.next(), Some(Ok(0x20)));
+            assert_eq!(scanner.next(), Some(Err(ErrorKind::ArbitrationLoss)));
+            assert_eq!(scanner.next(), Some(Ok(0x40)));
+            assert_eq!(scanner.next(), None);
+        }
+
+        assert_eq!(
+            scan(&mut bus, Probe::OneByteRead),
+            Err(ErrorKind::ArbitrationLoss)
+        );
+    }
+}
@@ -0,0 +1,404 @@
+//! Sharing an I2C bus between several drivers
+//!
+//! Drivers usually take ownership of the bus they talk over, which makes it impossible to hand a
+//! single I2C peripheral to several of them. The proxies in this module solve that: the bus is
+//! placed in a [`BusMutex`] and each driver receives its own [`I2cProxy`] that implements the
+//! blocking I2C traits by locking the mutex for the duration of each transfer.
+//!
+//! Three mutex flavours are provided:
+//!
+//! - `core::cell::RefCell` for buses that are only used from a single execution context.
+//! - [`CsMutex`] for buses that are shared with interrupt handlers. Each transfer runs inside a
+//!   critical section provided by the platform through the [`CriticalSection`] trait.
+//! - `std::sync::Mutex`, for hosted platforms, when embedded-hal is built with the `"std"` feature.
+//!
+//! *This module is available if embedded-hal is built with the `"unproven"` feature.*
+//!
+//! [`BusMutex`]: trait.BusMutex.html
+//! [`I2cProxy`]: struct.I2cProxy.html
+//! [`CsMutex`]: struct.CsMutex.html
+//! [`CriticalSection`]: trait.CriticalSection.html
+//!
+//! ```
+//! extern crate embedded_hal as hal;
+//!
+//! use std::cell::RefCell;
+//! use hal::blocking::i2c::{Write, WriteRead};
+//! use hal::blocking::i2c::shared::I2cProxy;
+//!
+//! /// Some driver that wants to own its bus
+//! struct Sensor<I2C> {
+//!     i2c: I2C,
+//! }
+//!
+//! impl<I2C> Sensor<I2C>
+//! where
+//!     I2C: WriteRead,
+//! {
+//!     fn temperature(&mut self) -> Result<u8, I2C::Error> {
+//!         let mut buffer = [0];
+//!         self.i2c.write_read(0x48, &[0x00], &mut buffer)?;
+//!         Ok(buffer[0])
+//!     }
+//! }
+//!
+//! fn main() {
+//!     let i2c: I2c1 = {
+//!         // ..
+//! #       I2c1
+//!     };
+//!
+//!     let bus = RefCell::new(i2c);
+//!     let mut sensor = Sensor { i2c: I2cProxy::new(&bus) };
+//!     let mut display = I2cProxy::new(&bus);
+//!
+//!     let temperature = sensor.temperature().unwrap();
+//!     display.write(0x3c, &[0x40, temperature]).unwrap();
+//! }
+//!
+//! # struct I2c1;
+//! # impl hal::blocking::i2c::Write for I2c1 {
+//! #     type Error = ();
+//! #     fn write(&mut self, _: u8, _: &[u8]) -> Result<(), ()> { Ok(()) }
+//! # }
+//! # impl hal::blocking::i2c::WriteRead for I2c1 {
+//! #     type Error = ();
+//! #     fn write_read(&mut self, _: u8, _: &[u8], _: &mut [u8]) -> Result<(), ()> { Ok(()) }
+//! # }
+//! ```
+
+use core::cell::RefCell;
+use core::marker::PhantomData;
+
+use super::{Read, Write, WriteIter, WriteIterRead, WriteRead};
+
+/// A mutex that protects a shared bus
+pub trait BusMutex {
+    /// The protected bus
+    type Bus;
+
+    /// Runs `f` with exclusive access to the bus
+    fn lock<R, F>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut Self::Bus) -> R;
+}
+
+/// Single context mutex
+///
+/// # Panics
+///
+/// If the bus is locked again from within `f`
+impl<T> BusMutex for RefCell<T> {
+    type Bus = T;
+
+    fn lock<R, F>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        f(&mut self.borrow_mut())
+    }
+}
+
+/// A platform specific critical section
+///
+/// # Safety
+///
+/// `with` must run `f` in a context that can't be preempted by any other code that may access a
+/// [`CsMutex`](struct.CsMutex.html), e.g. with interrupts disabled on a single core system.
+pub unsafe trait CriticalSection {
+    /// Runs `f` inside a critical section
+    fn with<R, F>(f: F) -> R
+    where
+        F: FnOnce() -> R;
+}
+
+/// Mutex based on critical sections
+///
+/// This mutex can be placed in a `static` and shared between the main program and interrupt
+/// handlers.
+///
+/// # Panics
+///
+/// If the bus is locked again from within `f`
+pub struct CsMutex<T, CS> {
+    bus: RefCell<T>,
+    _cs: PhantomData<CS>,
+}
+
+impl<T, CS> CsMutex<T, CS> {
+    /// Creates a new mutex that protects `bus`
+    pub const fn new(bus: T) -> Self {
+        CsMutex {
+            bus: RefCell::new(bus),
+            _cs: PhantomData,
+        }
+    }
+
+    /// Releases the bus
+    pub fn into_inner(self) -> T {
+        self.bus.into_inner()
+    }
+}
+
+// NOTE(unsafe) all accesses to `bus` happen inside a critical section
+unsafe impl<T, CS> Sync for CsMutex<T, CS>
+where
+    T: Send,
+    CS: CriticalSection,
+{
+}
+
+impl<T, CS> BusMutex for CsMutex<T, CS>
+where
+    CS: CriticalSection,
+{
+    type Bus = T;
+
+    fn lock<R, F>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        CS::with(|| f(&mut self.bus.borrow_mut()))
+    }
+}
+
+/// Multi-threaded mutex
+///
+/// *This implementation is available if embedded-hal is built with the `"std"` feature.*
+///
+/// # Panics
+///
+/// If the mutex has been poisoned
+#[cfg(feature = "std")]
+impl<T> BusMutex for ::std::sync::Mutex<T> {
+    type Bus = T;
+
+    fn lock<R, F>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        f(&mut self.lock().unwrap())
+    }
+}
+
+/// Handle to a shared I2C bus
+///
+/// Each blocking I2C operation locks the bus for the whole transfer so transfers issued through
+/// different proxies are never interleaved. Use [`transaction`] to run several transfers without
+/// other proxies accessing the bus in between.
+///
+/// [`transaction`]: #method.transaction
+pub struct I2cProxy<'a, M>
+where
+    M: 'a,
+{
+    mutex: &'a M,
+}
+
+impl<'a, M> I2cProxy<'a, M>
+where
+    M: BusMutex,
+{
+    /// Creates a new proxy for the bus protected by `mutex`
+    pub fn new(mutex: &'a M) -> Self {
+        I2cProxy { mutex }
+    }
+
+    /// Runs `f` with exclusive access to the bus
+    pub fn transaction<R, F>(&mut self, f: F) -> R
+    where
+        F: FnOnce(&mut M::Bus) -> R,
+    {
+        self.mutex.lock(f)
+    }
+}
+
+impl<'a, M> Clone for I2cProxy<'a, M> {
+    fn clone(&self) -> Self {
+        I2cProxy { mutex: self.mutex }
+    }
+}
+
+impl<'a, M> Read for I2cProxy<'a, M>
+where
+    M: BusMutex,
+    M::Bus: Read,
+{
+    type Error = <M::Bus as Read>::Error;
+
+    fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        self.mutex.lock(|bus| bus.read(address, buffer))
+    }
+}
+
+impl<'a, M> Write for I2cProxy<'a, M>
+where
+    M: BusMutex,
+    M::Bus: Write,
+{
+    type Error = <M::Bus as Write>::Error;
+
+    fn write(&mut self, addr: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.mutex.lock(|bus| bus.write(addr, bytes))
+    }
+}
+
+impl<'a, M> WriteIter for I2cProxy<'a, M>
+where
+    M: BusMutex,
+    M::Bus: WriteIter,
+{
+    type Error = <M::Bus as WriteIter>::Error;
+
+    fn write<B>(&mut self, addr: u8, bytes: B) -> Result<(), Self::Error>
+    where
+        B: IntoIterator<Item = u8>,
+    {
+        self.mutex.lock(|bus| bus.write(addr, bytes))
+    }
+}
+
+impl<'a, M> WriteRead for I2cProxy<'a, M>
+where
+    M: BusMutex,
+    M::Bus: WriteRead,
+{
+    type Error = <M::Bus as WriteRead>::Error;
+
+    fn write_read(
+        &mut self,
+        address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        self.mutex
+            .lock(|bus| bus.write_read(address, bytes, buffer))
+    }
+}
+
+impl<'a, M> WriteIterRead for I2cProxy<'a, M>
+where
+    M: BusMutex,
+    M::Bus: WriteIterRead,
+{
+    type Error = <M::Bus as WriteIterRead>::Error;
+
+    fn write_iter_read<B>(
+        &mut self,
+        address: u8,
+        bytes: B,
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error>
+    where
+        B: IntoIterator<Item = u8>,
+    {
+        self.mutex
+            .lock(|bus| bus.write_iter_read(address, bytes, buffer))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Records the address of every transfer
+    #[derive(Default)]
+    struct Bus {
+        log: [u8; 8],
+        len: usize,
+    }
+
+    impl Write for Bus {
+        type Error = ();
+
+        fn write(&mut self, addr: u8, _: &[u8]) -> Result<(), ()> {
+            self.log[self.len] = addr;
+            self.len += 1;
+            Ok(())
+        }
+    }
+
+    impl WriteRead for Bus {
+        type Error = ();
+
+        fn write_read(&mut self, address: u8, _: &[u8], buffer: &mut [u8]) -> Result<(), ()> {
+            self.write(address, &[])?;
+            for byte in buffer.iter_mut() {
+                *byte = address;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn ref_cell() {
+        let bus = RefCell::new(Bus::default());
+        let mut a = I2cProxy::new(&bus);
+        let mut b = a.clone();
+
+        a.write(0x10, &[]).unwrap();
+        let mut buffer = [0; 2];
+        b.write_read(0x20, &[], &mut buffer).unwrap();
+        a.transaction(|bus| {
+            bus.write(0x10, &[]).unwrap();
+            bus.write(0x11, &[]).unwrap();
+        });
+
+        assert_eq!(buffer, [0x20, 0x20]);
+        let bus = bus.into_inner();
+        assert_eq!(&bus.log[..bus.len], &[0x10, 0x20, 0x10, 0x11]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn ref_cell_nested_lock() {
+        let bus = RefCell::new(Bus::default());
+        let mut a = I2cProxy::new(&bus);
+        let mut b = I2cProxy::new(&bus);
+
+        a.transaction(|_| b.write(0x10, &[])).unwrap();
+    }
+
+    struct Cs;
+
+    static SECTIONS: AtomicUsize = AtomicUsize::new(0);
+
+    unsafe impl CriticalSection for Cs {
+        fn with<R, F>(f: F) -> R
+        where
+            F: FnOnce() -> R,
+        {
+            SECTIONS.fetch_add(1, Ordering::SeqCst);
+            f()
+        }
+    }
+
+    #[test]
+    fn critical_section() {
+        let bus: CsMutex<Bus, Cs> = CsMutex::new(Bus::default());
+        let mut a = I2cProxy::new(&bus);
+        let mut b = I2cProxy::new(&bus);
+
+        a.write(0x10, &[]).unwrap();
+        b.write(0x20, &[]).unwrap();
+
+        assert_eq!(SECTIONS.load(Ordering::SeqCst), 2);
+        let bus = bus.into_inner();
+        assert_eq!(&bus.log[..bus.len], &[0x10, 0x20]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn std_mutex() {
+        use std::sync::Mutex;
+
+        let bus = Mutex::new(Bus::default());
+        let mut a = I2cProxy::new(&bus);
+
+        a.write(0x10, &[]).unwrap();
+
+        assert_eq!(bus.into_inner().unwrap().len, 1);
+    }
+}
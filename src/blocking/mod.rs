@@ -6,6 +6,8 @@
 
 pub mod delay;
 pub mod i2c;
+#[cfg(feature = "unproven")]
+pub mod register;
 pub mod rng;
 pub mod serial;
 pub mod spi;
@@ -0,0 +1,573 @@
+//! Register access over I2C and SPI
+//!
+//! Most sensors and peripheral chips expose their functionality as a map of 8-bit registers. The
+//! [`RegisterInterface`] trait abstracts over the bus used to reach those registers so drivers can
+//! be written once and used over either [`I2cInterface`] or [`SpiInterface`].
+//!
+//! *This module is available if embedded-hal is built with the `"unproven"` feature.*
+//!
+//! [`RegisterInterface`]: trait.RegisterInterface.html
+//! [`I2cInterface`]: struct.I2cInterface.html
+//! [`SpiInterface`]: struct.SpiInterface.html
+//!
+//! ```
+//! extern crate embedded_hal as hal;
+//!
+//! use hal::blocking::register::{Config, I2cInterface, RegisterInterface};
+//!
+//! const CTRL_REG1: u16 = 0x20;
+//! const OUT_X_L: u16 = 0x28;
+//!
+//! fn main() {
+//!     let i2c: I2c1 = {
+//!         // ..
+//! #       I2c1
+//!     };
+//!
+//!     let config = Config {
+//!         // set the MSB of the register address to read several registers in one go
+//!         auto_increment: 0x80,
+//!         ..Config::default()
+//!     };
+//!     let mut accel = I2cInterface::new(i2c, 0x19, config);
+//!
+//!     // power on, leaving the other bits untouched
+//!     accel.modify_register(CTRL_REG1, |ctrl| ctrl | 0x07).unwrap();
+//!
+//!     let mut xyz = [0; 6];
+//!     accel.read_registers(OUT_X_L, &mut xyz).unwrap();
+//! }
+//!
+//! # struct I2c1;
+//! # impl hal::blocking::i2c::WriteRead for I2c1 {
+//! #     type Error = ();
+//! #     fn write_read(&mut self, _: u8, _: &[u8], _: &mut [u8]) -> Result<(), ()> { Ok(()) }
+//! # }
+//! # impl hal::blocking::i2c::Write for I2c1 {
+//! #     type Error = ();
+//! #     fn write(&mut self, _: u8, _: &[u8]) -> Result<(), ()> { Ok(()) }
+//! # }
+//! ```
+
+use blocking::i2c;
+use blocking::spi;
+use digital::v2::OutputPin;
+
+/// Size of register addresses
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AddressWidth {
+    /// One byte addresses
+    ///
+    /// Register addresses, including their flags, must not exceed `0xff`; accesses to larger
+    /// addresses panic.
+    Bits8,
+    /// Two byte addresses
+    Bits16,
+}
+
+/// Byte order
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Endianness {
+    /// Most significant byte first
+    Big,
+    /// Least significant byte first
+    Little,
+}
+
+/// Register interface configuration
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Config {
+    /// Size of the register addresses
+    pub address_width: AddressWidth,
+    /// Order in which the bytes of 16-bit addresses and values are sent
+    pub endianness: Endianness,
+    /// Bits set in the register address when more than one register is accessed
+    ///
+    /// Some devices only auto-increment the register address when told to, e.g. by setting the
+    /// MSB of the address.
+    pub auto_increment: u16,
+    /// Bits set in the register address when reading (SPI only)
+    pub read_flag: u16,
+    /// Bits set in the register address when writing (SPI only)
+    pub write_flag: u16,
+}
+
+impl Default for Config {
+    /// 8-bit big endian addresses, no flags
+    fn default() -> Self {
+        Config {
+            address_width: AddressWidth::Bits8,
+            endianness: Endianness::Big,
+            auto_increment: 0,
+            read_flag: 0,
+            write_flag: 0,
+        }
+    }
+}
+
+impl Config {
+    /// Encodes `register` and `flags` into `buffer`, returning the number of bytes used
+    fn encode_address(&self, register: u16, flags: u16, buffer: &mut [u8]) -> usize {
+        let address = register | flags;
+        match self.address_width {
+            AddressWidth::Bits8 => {
+                assert!(address <= 0xff, "register address doesn't fit in 8 bits");
+                buffer[0] = address as u8;
+                1
+            }
+            AddressWidth::Bits16 => {
+                buffer[..2].copy_from_slice(&self.encode_u16(address));
+                2
+            }
+        }
+    }
+
+    fn encode_u16(&self, value: u16) -> [u8; 2] {
+        match self.endianness {
+            Endianness::Big => [(value >> 8) as u8, value as u8],
+            Endianness::Little => [value as u8, (value >> 8) as u8],
+        }
+    }
+
+    fn decode_u16(&self, bytes: [u8; 2]) -> u16 {
+        match self.endianness {
+            Endianness::Big => u16::from(bytes[0]) << 8 | u16::from(bytes[1]),
+            Endianness::Little => u16::from(bytes[1]) << 8 | u16::from(bytes[0]),
+        }
+    }
+
+    fn burst_flags(&self, len: usize) -> u16 {
+        if len > 1 {
+            self.auto_increment
+        } else {
+            0
+        }
+    }
+}
+
+/// Access to the registers of a device
+pub trait RegisterInterface {
+    /// Error type
+    type Error;
+
+    /// Returns the configuration of this interface
+    fn config(&self) -> &Config;
+
+    /// Reads consecutive registers, starting at `register`, to fill `buffer`
+    fn read_registers(&mut self, register: u16, buffer: &mut [u8]) -> Result<(), Self::Error>;
+
+    /// Writes `data` to consecutive registers, starting at `register`
+    fn write_registers(&mut self, register: u16, data: &[u8]) -> Result<(), Self::Error>;
+
+    /// Reads a single register
+    fn read_register(&mut self, register: u16) -> Result<u8, Self::Error> {
+        let mut buffer = [0];
+        self.read_registers(register, &mut buffer)?;
+        Ok(buffer[0])
+    }
+
+    /// Writes a single register
+    fn write_register(&mut self, register: u16, value: u8) -> Result<(), Self::Error> {
+        self.write_registers(register, &[value])
+    }
+
+    /// Reads `register`, passes its value to `f` and writes back the value `f` returns
+    fn modify_register<F>(&mut self, register: u16, f: F) -> Result<(), Self::Error>
+    where
+        F: FnOnce(u8) -> u8,
+    {
+        let value = self.read_register(register)?;
+        self.write_register(register, f(value))
+    }
+
+    /// Sets the bits of `register` that are set in `mask`
+    fn set_bits(&mut self, register: u16, mask: u8) -> Result<(), Self::Error> {
+        self.modify_register(register, |value| value | mask)
+    }
+
+    /// Clears the bits of `register` that are set in `mask`
+    fn clear_bits(&mut self, register: u16, mask: u8) -> Result<(), Self::Error> {
+        self.modify_register(register, |value| value & !mask)
+    }
+
+    /// Reads a 16-bit value spread over two consecutive registers
+    ///
+    /// The byte order is given by the `endianness` of the interface configuration.
+    fn read_u16(&mut self, register: u16) -> Result<u16, Self::Error> {
+        let mut buffer = [0; 2];
+        self.read_registers(register, &mut buffer)?;
+        Ok(self.config().decode_u16(buffer))
+    }
+
+    /// Writes a 16-bit value to two consecutive registers
+    ///
+    /// The byte order is given by the `endianness` of the interface configuration.
+    fn write_u16(&mut self, register: u16, value: u16) -> Result<(), Self::Error> {
+        let bytes = self.config().encode_u16(value);
+        self.write_registers(register, &bytes)
+    }
+}
+
+/// Register interface over I2C
+///
+/// Reads are done with a single `WriteRead` transaction; writes send the register address
+/// followed by the data with `Write`. Writes of more than 16 bytes are split into several
+/// transactions, each addressing the register that follows the previous chunk, so they are not
+/// atomic: another bus master, or a failed transaction, can leave the device with only part of
+/// the data written.
+pub struct I2cInterface<I2C> {
+    i2c: I2C,
+    address: u8,
+    config: Config,
+}
+
+impl<I2C, E> I2cInterface<I2C>
+where
+    I2C: i2c::WriteRead<Error = E> + i2c::Write<Error = E>,
+{
+    /// Creates an interface to the device with I2C `address`
+    pub fn new(i2c: I2C, address: u8, config: Config) -> Self {
+        I2cInterface {
+            i2c,
+            address,
+            config,
+        }
+    }
+
+    /// Releases the I2C bus
+    pub fn free(self) -> I2C {
+        self.i2c
+    }
+}
+
+impl<I2C, E> RegisterInterface for I2cInterface<I2C>
+where
+    I2C: i2c::WriteRead<Error = E> + i2c::Write<Error = E>,
+{
+    type Error = E;
+
+    fn config(&self) -> &Config {
+        &self.config
+    }
+
+    fn read_registers(&mut self, register: u16, buffer: &mut [u8]) -> Result<(), E> {
+        let mut address = [0; 2];
+        let flags = self.config.burst_flags(buffer.len());
+        let n = self.config.encode_address(register, flags, &mut address);
+
+        self.i2c.write_read(self.address, &address[..n], buffer)
+    }
+
+    fn write_registers(&mut self, register: u16, data: &[u8]) -> Result<(), E> {
+        // register address followed by up to 16 bytes of data
+        let mut buffer = [0; 18];
+        let mut register = register;
+        for chunk in data.chunks(16) {
+            let flags = self.config.burst_flags(chunk.len());
+            let n = self.config.encode_address(register, flags, &mut buffer);
+            buffer[n..n + chunk.len()].copy_from_slice(chunk);
+
+            i2c::Write::write(&mut self.i2c, self.address, &buffer[..n + chunk.len()])?;
+            register = register.wrapping_add(chunk.len() as u16);
+        }
+
+        Ok(())
+    }
+}
+
+/// Error of a register interface over SPI
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SpiError<SPI, PIN> {
+    /// SPI bus error
+    Spi(SPI),
+    /// Chip select pin error
+    Pin(PIN),
+}
+
+/// Register interface over SPI
+///
+/// Chip select is asserted (driven low) for the duration of each access. The read and write flags
+/// of the configuration are set in the register address, which is sent before the data.
+pub struct SpiInterface<SPI, CS> {
+    spi: SPI,
+    cs: CS,
+    config: Config,
+}
+
+impl<SPI, CS> SpiInterface<SPI, CS>
+where
+    SPI: spi::Transfer<u8>,
+    CS: OutputPin,
+{
+    /// Creates an interface to the device selected by `cs`
+    pub fn new(spi: SPI, cs: CS, config: Config) -> Self {
+        SpiInterface { spi, cs, config }
+    }
+
+    /// Releases the SPI bus and the chip select pin
+    pub fn free(self) -> (SPI, CS) {
+        (self.spi, self.cs)
+    }
+
+    fn select<F>(&mut self, f: F) -> Result<(), SpiError<SPI::Error, CS::Error>>
+    where
+        F: FnOnce(&mut SPI) -> Result<(), SPI::Error>,
+    {
+        self.cs.set_low().map_err(SpiError::Pin)?;
+        let result = f(&mut self.spi).map_err(SpiError::Spi);
+        // deassert chip select even if the transfer failed
+        let deselect = self.cs.set_high().map_err(SpiError::Pin);
+
+        result.and(deselect)
+    }
+}
+
+impl<SPI, CS> RegisterInterface for SpiInterface<SPI, CS>
+where
+    SPI: spi::Transfer<u8>,
+    CS: OutputPin,
+{
+    type Error = SpiError<SPI::Error, CS::Error>;
+
+    fn config(&self) -> &Config {
+        &self.config
+    }
+
+    fn read_registers(&mut self, register: u16, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        let mut address = [0; 2];
+        let flags = self.config.read_flag | self.config.burst_flags(buffer.len());
+        let n = self.config.encode_address(register, flags, &mut address);
+
+        for byte in buffer.iter_mut() {
+            *byte = 0;
+        }
+
+        self.select(|spi| {
+            spi.transfer(&mut address[..n])?;
+            spi.transfer(buffer)?;
+            Ok(())
+        })
+    }
+
+    fn write_registers(&mut self, register: u16, data: &[u8]) -> Result<(), Self::Error> {
+        let mut address = [0; 2];
+        let flags = self.config.write_flag | self.config.burst_flags(data.len());
+        let n = self.config.encode_address(register, flags, &mut address);
+
+        self.select(|spi| {
+            spi.transfer(&mut address[..n])?;
+
+            // `Transfer` needs a mutable buffer
+            let mut chunk = [0; 16];
+            for bytes in data.chunks(chunk.len()) {
+                let chunk = &mut chunk[..bytes.len()];
+                chunk.copy_from_slice(bytes);
+                spi.transfer(chunk)?;
+            }
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::cell::Cell;
+
+    /// Device with 256 registers that auto-increments when the address MSB is set
+    struct Device {
+        registers: [u8; 256],
+        pointer: usize,
+        increment: bool,
+    }
+
+    impl Device {
+        fn new() -> Self {
+            let mut registers = [0; 256];
+            for (i, register) in registers.iter_mut().enumerate() {
+                *register = i as u8;
+            }
+
+            Device {
+                registers,
+                pointer: 0,
+                increment: false,
+            }
+        }
+
+        fn set_pointer(&mut self, address: u8) {
+            self.pointer = usize::from(address & 0x7f);
+            self.increment = address & 0x80 != 0;
+        }
+
+        fn next(&mut self) -> &mut u8 {
+            let register = &mut self.registers[self.pointer];
+            if self.increment {
+                self.pointer += 1;
+            }
+            register
+        }
+    }
+
+    impl i2c::WriteRead for Device {
+        type Error = ();
+
+        fn write_read(&mut self, address: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), ()> {
+            assert_eq!(address, 0x19);
+            assert_eq!(bytes.len(), 1);
+            self.set_pointer(bytes[0]);
+            for byte in buffer {
+                *byte = *self.next();
+            }
+            Ok(())
+        }
+    }
+
+    impl i2c::Write for Device {
+        type Error = ();
+
+        fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), ()> {
+            assert_eq!(address, 0x19);
+            self.set_pointer(bytes[0]);
+            for byte in &bytes[1..] {
+                *self.next() = *byte;
+            }
+            Ok(())
+        }
+    }
+
+    fn auto_increment() -> Config {
+        Config {
+            auto_increment: 0x80,
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn i2c_burst_read() {
+        let mut device = I2cInterface::new(Device::new(), 0x19, auto_increment());
+
+        let mut buffer = [0; 3];
+        device.read_registers(0x28, &mut buffer).unwrap();
+        assert_eq!(buffer, [0x28, 0x29, 0x2a]);
+
+        // a single register is read without the auto-increment flag
+        assert_eq!(device.read_register(0x28), Ok(0x28));
+        assert!(!device.free().increment);
+    }
+
+    #[test]
+    fn i2c_modify_and_u16() {
+        let mut device = I2cInterface::new(Device::new(), 0x19, auto_increment());
+
+        device.modify_register(0x20, |value| value | 0x07).unwrap();
+        device.clear_bits(0x21, 0x01).unwrap();
+        device.write_u16(0x30, 0x1234).unwrap();
+
+        let registers = device.free().registers;
+        assert_eq!(registers[0x20], 0x27);
+        assert_eq!(registers[0x21], 0x20);
+        assert_eq!(&registers[0x30..0x32], &[0x12, 0x34]);
+    }
+
+    #[test]
+    fn i2c_long_write() {
+        let mut device = I2cInterface::new(Device::new(), 0x19, auto_increment());
+
+        let data = [0xee; 20];
+        device.write_registers(0x40, &data).unwrap();
+
+        let registers = device.free().registers;
+        assert_eq!(registers[0x3f], 0x3f);
+        assert_eq!(&registers[0x40..0x54], &data[..]);
+        assert_eq!(registers[0x54], 0x54);
+    }
+
+    #[test]
+    #[should_panic]
+    fn address_too_wide() {
+        let mut device = I2cInterface::new(Device::new(), 0x19, Config::default());
+
+        let _ = device.read_register(0x100);
+    }
+
+    #[test]
+    fn little_endian_u16() {
+        let config = Config {
+            endianness: Endianness::Little,
+            ..auto_increment()
+        };
+        let mut device = I2cInterface::new(Device::new(), 0x19, config);
+
+        assert_eq!(device.read_u16(0x10), Ok(0x1110));
+    }
+
+    /// Records everything that's sent while chip select is low
+    struct Spi<'a> {
+        sent: [u8; 32],
+        len: usize,
+        selected: &'a Cell<bool>,
+    }
+
+    impl<'a> spi::Transfer<u8> for Spi<'a> {
+        type Error = ();
+
+        fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], ()> {
+            assert!(self.selected.get());
+            for word in words.iter_mut() {
+                self.sent[self.len] = *word;
+                self.len += 1;
+                *word = 0xaa;
+            }
+            Ok(words)
+        }
+    }
+
+    struct ChipSelect<'a>(&'a Cell<bool>);
+
+    impl<'a> OutputPin for ChipSelect<'a> {
+        type Error = ();
+
+        fn set_low(&mut self) -> Result<(), ()> {
+            self.0.set(true);
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), ()> {
+            self.0.set(false);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn spi_flags() {
+        let config = Config {
+            address_width: AddressWidth::Bits16,
+            endianness: Endianness::Big,
+            auto_increment: 0x4000,
+            read_flag: 0x8000,
+            write_flag: 0x0000,
+        };
+        let selected = Cell::new(false);
+        let spi = Spi {
+            sent: [0; 32],
+            len: 0,
+            selected: &selected,
+        };
+        let mut device = SpiInterface::new(spi, ChipSelect(&selected), config);
+
+        let mut buffer = [0x55; 2];
+        device.read_registers(0x0102, &mut buffer).unwrap();
+        assert_eq!(buffer, [0xaa, 0xaa]);
+        device.write_register(0x0304, 0x42).unwrap();
+
+        let (spi, _) = device.free();
+        assert!(!selected.get());
+        assert_eq!(
+            &spi.sent[..spi.len],
+            &[0xc1, 0x02, 0x00, 0x00, 0x03, 0x04, 0x42]
+        );
+    }
+}
@@ -1,5 +1,8 @@
 //! Blocking serial API
 
+#[cfg(feature = "unproven")]
+use timer::CountDown;
+
 /// Write half of a serial interface (blocking variant)
 pub trait Write<Word> {
     /// The type of error that can occur when writing
@@ -19,6 +22,38 @@ pub trait Write<Word> {
     fn bflush(&mut self) -> Result<(), Self::Error>;
 }
 
+/// Read half of a serial interface (blocking variant)
+///
+/// *This trait is available if embedded-hal is built with the `"unproven"` feature.*
+#[cfg(feature = "unproven")]
+pub trait Read<Word> {
+    /// The type of error that can occur when reading
+    type Error;
+
+    /// Reads words, blocking until `buffer` has been filled
+    fn bread_exact(&mut self, buffer: &mut [Word]) -> Result<(), Self::Error>;
+
+    /// Reads words into `buffer`, blocking until `delimiter` has been received or `buffer` is full
+    ///
+    /// Returns the number of words stored in `buffer`, including the delimiter. If `buffer` fills
+    /// up before the delimiter arrives `buffer.len()` is returned and the last word stored is not
+    /// the delimiter.
+    fn bread_until(&mut self, delimiter: Word, buffer: &mut [Word]) -> Result<usize, Self::Error>;
+
+    /// Reads words into `buffer`, blocking until it's full or `timer` expires
+    ///
+    /// `timer` must have been started by the caller; it acts as a deadline for the whole read.
+    /// Returns the number of words stored in `buffer`, which is less than `buffer.len()` if the
+    /// deadline was reached.
+    fn bread_timeout<T>(
+        &mut self,
+        buffer: &mut [Word],
+        timer: &mut T,
+    ) -> Result<usize, Self::Error>
+    where
+        T: CountDown;
+}
+
 /// Blocking serial write
 pub mod write {
     /// Marker trait to opt into default blocking write implementation
@@ -52,3 +87,183 @@ pub mod write {
         }
     }
 }
+
+/// Blocking serial read
+///
+/// *This module is available if embedded-hal is built with the `"unproven"` feature.*
+#[cfg(feature = "unproven")]
+pub mod read {
+    use timer::CountDown;
+
+    /// Marker trait to opt into default blocking read implementation
+    ///
+    /// Implementers of [`serial::Read`] can implement this marker trait
+    /// for their type. Doing so will automatically provide the default
+    /// implementation of [`blocking::serial::Read`] for the type.
+    ///
+    /// [`serial::Read`]: ../../serial/trait.Read.html
+    /// [`blocking::serial::Read`]: ../trait.Read.html
+    pub trait Default<Word>: ::serial::Read<Word> {}
+
+    impl<S, Word> ::blocking::serial::Read<Word> for S
+    where
+        S: Default<Word>,
+        Word: PartialEq,
+    {
+        type Error = S::Error;
+
+        fn bread_exact(&mut self, buffer: &mut [Word]) -> Result<(), Self::Error> {
+            for word in buffer {
+                *word = block!(self.read())?;
+            }
+
+            Ok(())
+        }
+
+        fn bread_until(
+            &mut self,
+            delimiter: Word,
+            buffer: &mut [Word],
+        ) -> Result<usize, Self::Error> {
+            for (i, word) in buffer.iter_mut().enumerate() {
+                *word = block!(self.read())?;
+                if *word == delimiter {
+                    return Ok(i + 1);
+                }
+            }
+
+            Ok(buffer.len())
+        }
+
+        fn bread_timeout<T>(
+            &mut self,
+            buffer: &mut [Word],
+            timer: &mut T,
+        ) -> Result<usize, Self::Error>
+        where
+            T: CountDown,
+        {
+            let mut n = 0;
+            while n < buffer.len() {
+                match self.read() {
+                    Ok(word) => {
+                        buffer[n] = word;
+                        n += 1;
+                        continue;
+                    }
+                    Err(::nb::Error::Other(e)) => return Err(e),
+                    Err(::nb::Error::WouldBlock) => {}
+                }
+
+                if timer.wait().is_ok() {
+                    break;
+                }
+            }
+
+            Ok(n)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use blocking::serial::Read;
+        use void::Void;
+
+        use nb;
+
+        /// Serial interface that receives `data`, one word every other poll
+        struct Serial<'a> {
+            data: &'a [u8],
+            polls: usize,
+        }
+
+        impl<'a> ::serial::Read<u8> for Serial<'a> {
+            type Error = ();
+
+            fn read(&mut self) -> nb::Result<u8, ()> {
+                self.polls += 1;
+                if self.polls % 2 == 1 {
+                    return Err(nb::Error::WouldBlock);
+                }
+
+                match self.data.split_first() {
+                    Some((&first, rest)) => {
+                        self.data = rest;
+                        Ok(first)
+                    }
+                    None => Err(nb::Error::WouldBlock),
+                }
+            }
+        }
+
+        impl<'a> super::Default<u8> for Serial<'a> {}
+
+        /// Timer that expires on the `n`th call to `wait`
+        struct Timer(usize);
+
+        impl ::timer::CountDown for Timer {
+            type Time = ();
+
+            fn start<T>(&mut self, _: T)
+            where
+                T: Into<()>,
+            {
+            }
+
+            fn wait(&mut self) -> nb::Result<(), Void> {
+                self.0 -= 1;
+                if self.0 == 0 {
+                    Ok(())
+                } else {
+                    Err(nb::Error::WouldBlock)
+                }
+            }
+        }
+
+        #[test]
+        fn read_exact() {
+            let mut serial = Serial {
+                data: b"hello",
+                polls: 0,
+            };
+            let mut buffer = [0; 4];
+
+            serial.bread_exact(&mut buffer).unwrap();
+            assert_eq!(&buffer, b"hell");
+        }
+
+        #[test]
+        fn read_until() {
+            let mut serial = Serial {
+                data: b"$GPGGA\r\n$GP",
+                polls: 0,
+            };
+            let mut buffer = [0; 16];
+
+            assert_eq!(serial.bread_until(b'\n', &mut buffer), Ok(8));
+            assert_eq!(&buffer[..8], b"$GPGGA\r\n");
+
+            let mut buffer = [0; 2];
+            assert_eq!(serial.bread_until(b'\n', &mut buffer), Ok(2));
+            assert_eq!(&buffer, b"$G");
+        }
+
+        #[test]
+        fn read_timeout() {
+            let mut serial = Serial {
+                data: b"abc",
+                polls: 0,
+            };
+            let mut buffer = [0; 8];
+
+            assert_eq!(serial.bread_timeout(&mut buffer, &mut Timer(100)), Ok(3));
+            assert_eq!(&buffer[..3], b"abc");
+
+            let mut serial = Serial {
+                data: b"abcdef",
+                polls: 0,
+            };
+            assert_eq!(serial.bread_timeout(&mut buffer, &mut Timer(2)), Ok(1));
+        }
+    }
+}
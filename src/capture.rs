@@ -0,0 +1,422 @@
+//! Measurements on top of the `Capture` trait
+//!
+//! [`Meter`] turns the raw counter values returned by a [`Capture`] interface into periods,
+//! frequencies, pulse widths and duty cycles. Captures are taken modulo the counter range, so
+//! each measured interval must be shorter than one full turn of the counter.
+//!
+//! *This module is available if embedded-hal is built with the `"unproven"` feature.*
+//!
+//! [`Meter`]: struct.Meter.html
+//! [`Capture`]: ../trait.Capture.html
+
+use core::convert::TryFrom;
+
+use time::{Hertz, Nanoseconds};
+use timer::{Instant, Ticks};
+use Capture;
+
+const NANOS_PER_SECOND: u64 = 1_000_000_000;
+
+/// Measurement error
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Error<E> {
+    /// The `Capture` interface returned an error
+    Capture(E),
+    /// The measured value doesn't fit in its unit; e.g. a period longer than `u32::MAX`
+    /// nanoseconds or a signal too fast to be resolved
+    Overflow,
+}
+
+/// Period and pulse width of a signal
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Waveform {
+    period: Nanoseconds,
+    pulse_width: Nanoseconds,
+}
+
+impl Waveform {
+    /// Returns the period of the signal
+    pub fn period(&self) -> Nanoseconds {
+        self.period
+    }
+
+    /// Returns the time the signal stays high every period
+    pub fn pulse_width(&self) -> Nanoseconds {
+        self.pulse_width
+    }
+
+    /// Returns the frequency of the signal, rounded to the nearest hertz
+    pub fn frequency(&self) -> Hertz {
+        // `Meter` never creates a waveform with a zero period
+        frequency::<()>(1, u64::from(self.period.0)).unwrap_or(Hertz(0))
+    }
+
+    /// Returns the duty cycle as a fraction of `full_scale`; e.g. pass `100` to get a percentage
+    pub fn duty_cycle(&self, full_scale: u32) -> u32 {
+        let duty = u64::from(self.pulse_width.0) * u64::from(full_scale) / u64::from(self.period.0);
+
+        if duty < u64::from(full_scale) {
+            duty as u32
+        } else {
+            full_scale
+        }
+    }
+}
+
+/// Measures signals with a `Capture` interface
+///
+/// All the measurements block until enough captures have been taken, and average over `samples`
+/// periods of the signal.
+///
+/// # Examples
+///
+/// ```
+/// extern crate embedded_hal as hal;
+///
+/// use hal::capture::Meter;
+/// use hal::time::Nanoseconds;
+///
+/// fn main() {
+///     let capture: Capture1 = {
+///         // ..
+/// #       Capture1(0)
+///     };
+///
+///     // 1 MHz counter
+///     let mut meter = Meter::new(capture, Nanoseconds(1_000));
+///
+///     let frequency = meter.frequency(Channel::_1, 8).unwrap();
+///     println!("{} Hz", frequency.0);
+///
+///     // channel 1 captures the rising edges and channel 2 the falling edges of the same signal
+///     let waveform = meter.waveform(Channel::_1, Channel::_2, 8).unwrap();
+///     println!("duty cycle: {}%", waveform.duty_cycle(100));
+/// }
+///
+/// # extern crate nb;
+/// # extern crate void;
+/// # #[derive(Clone, Copy)]
+/// # enum Channel { _1, _2 }
+/// # struct Capture1(u16);
+/// # impl hal::Capture for Capture1 {
+/// #     type Capture = u16;
+/// #     type Channel = Channel;
+/// #     type Error = void::Void;
+/// #     type Time = Nanoseconds;
+/// #     fn capture(&mut self, _: Channel) -> nb::Result<u16, void::Void> {
+/// #         self.0 = self.0.wrapping_add(250);
+/// #         Ok(self.0)
+/// #     }
+/// #     fn disable(&mut self, _: Channel) {}
+/// #     fn enable(&mut self, _: Channel) {}
+/// #     fn get_resolution(&self) -> Nanoseconds { Nanoseconds(1_000) }
+/// #     fn set_resolution<T>(&mut self, _: T) where T: Into<Nanoseconds> {}
+/// # }
+/// ```
+pub struct Meter<C> {
+    capture: C,
+    resolution: u32,
+}
+
+impl<C> Meter<C>
+where
+    C: Capture,
+    C::Capture: Ticks,
+    C::Channel: Copy,
+    C::Time: From<Nanoseconds> + Into<Nanoseconds>,
+{
+    /// Wraps `capture`, setting its resolution, i.e. the duration of one counter tick
+    ///
+    /// The measurements use the resolution the `Capture` interface reports after the change,
+    /// which may differ from `resolution` if the hardware can't match it exactly.
+    pub fn new(mut capture: C, resolution: Nanoseconds) -> Self {
+        capture.set_resolution(resolution);
+        let resolution = capture.get_resolution().into();
+
+        Meter {
+            capture,
+            resolution: resolution.0,
+        }
+    }
+
+    /// Releases the `Capture` interface
+    pub fn free(self) -> C {
+        self.capture
+    }
+
+    /// Measures the period of the signal on `channel`
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `samples` is zero.
+    pub fn period(
+        &mut self,
+        channel: C::Channel,
+        samples: u32,
+    ) -> Result<Nanoseconds, Error<C::Error>> {
+        let total = self.periods(channel, samples)?;
+
+        average(total, samples)
+    }
+
+    /// Measures the frequency of the signal on `channel`, rounded to the nearest hertz
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `samples` is zero.
+    pub fn frequency(
+        &mut self,
+        channel: C::Channel,
+        samples: u32,
+    ) -> Result<Hertz, Error<C::Error>> {
+        let total = self.periods(channel, samples)?;
+
+        frequency(samples, total)
+    }
+
+    /// Measures the time the signal stays high every period
+    ///
+    /// `rising` and `falling` must capture the rising and the falling edges of the same signal,
+    /// respectively, with the same counter.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `samples` is zero.
+    pub fn pulse_width(
+        &mut self,
+        rising: C::Channel,
+        falling: C::Channel,
+        samples: u32,
+    ) -> Result<Nanoseconds, Error<C::Error>> {
+        self.waveform(rising, falling, samples)
+            .map(|waveform| waveform.pulse_width)
+    }
+
+    /// Measures both the period and the pulse width of a signal
+    ///
+    /// See [`pulse_width`](#method.pulse_width) for the requirements on `rising` and `falling`.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `samples` is zero.
+    pub fn waveform(
+        &mut self,
+        rising: C::Channel,
+        falling: C::Channel,
+        samples: u32,
+    ) -> Result<Waveform, Error<C::Error>> {
+        assert!(samples > 0);
+
+        let mut start = self.capture(rising)?;
+        let mut period = 0;
+        let mut high = 0;
+        for _ in 0..samples {
+            let fall = self.capture(falling)?;
+            let rise = self.capture(rising)?;
+            high = add(high, fall.duration_since(start).into_u64())?;
+            period = add(period, rise.duration_since(start).into_u64())?;
+            start = rise;
+        }
+
+        let period = average(self.nanos(period)?, samples)?;
+        if period.0 == 0 {
+            return Err(Error::Overflow);
+        }
+
+        Ok(Waveform {
+            period,
+            pulse_width: average(self.nanos(high)?, samples)?,
+        })
+    }
+
+    // total length of `samples` periods, in nanoseconds
+    fn periods(&mut self, channel: C::Channel, samples: u32) -> Result<u64, Error<C::Error>> {
+        assert!(samples > 0);
+
+        let mut last = self.capture(channel)?;
+        let mut total = 0;
+        for _ in 0..samples {
+            let next = self.capture(channel)?;
+            total = add(total, next.duration_since(last).into_u64())?;
+            last = next;
+        }
+
+        self.nanos(total)
+    }
+
+    fn capture(&mut self, channel: C::Channel) -> Result<Instant<C::Capture>, Error<C::Error>> {
+        block!(self.capture.capture(channel))
+            .map(Instant::new)
+            .map_err(Error::Capture)
+    }
+
+    fn nanos<E>(&self, ticks: u64) -> Result<u64, Error<E>> {
+        ticks
+            .checked_mul(u64::from(self.resolution))
+            .ok_or(Error::Overflow)
+    }
+}
+
+fn add<E>(a: u64, b: u64) -> Result<u64, Error<E>> {
+    a.checked_add(b).ok_or(Error::Overflow)
+}
+
+fn average<E>(total: u64, samples: u32) -> Result<Nanoseconds, Error<E>> {
+    u32::try_from(total / u64::from(samples))
+        .map(Nanoseconds)
+        .map_err(|_| Error::Overflow)
+}
+
+// frequency of a signal whose `periods` periods last `nanos` nanoseconds
+fn frequency<E>(periods: u32, nanos: u64) -> Result<Hertz, Error<E>> {
+    if nanos == 0 {
+        return Err(Error::Overflow);
+    }
+
+    let cycles = u64::from(periods)
+        .checked_mul(NANOS_PER_SECOND)
+        .ok_or(Error::Overflow)?;
+    // round to nearest without adding to `cycles`, which could overflow
+    let (hz, rem) = (cycles / nanos, cycles % nanos);
+    let hz = if rem >= nanos - rem { hz + 1 } else { hz };
+    u32::try_from(hz).map(Hertz).map_err(|_| Error::Overflow)
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+
+    use nb;
+
+    use super::*;
+
+    /// Capture interface that replays captures; channel `true` is the rising edge
+    struct Capture1<'a> {
+        rising: &'a [u16],
+        falling: &'a [u16],
+        resolution: Cell<u32>,
+        // the resolution is rounded to the nearest multiple of `step`
+        step: u32,
+    }
+
+    impl<'a> Capture for Capture1<'a> {
+        type Capture = u16;
+        type Channel = bool;
+        type Error = ();
+        type Time = Nanoseconds;
+
+        fn capture(&mut self, rising: bool) -> nb::Result<u16, ()> {
+            let captures = if rising {
+                &mut self.rising
+            } else {
+                &mut self.falling
+            };
+            let (first, rest) = captures.split_first().ok_or(nb::Error::Other(()))?;
+            *captures = rest;
+            Ok(*first)
+        }
+
+        fn disable(&mut self, _: bool) {}
+
+        fn enable(&mut self, _: bool) {}
+
+        fn get_resolution(&self) -> Nanoseconds {
+            Nanoseconds(self.resolution.get())
+        }
+
+        fn set_resolution<T>(&mut self, resolution: T)
+        where
+            T: Into<Nanoseconds>,
+        {
+            let resolution = resolution.into().0;
+            self.resolution
+                .set((resolution + self.step / 2) / self.step * self.step);
+        }
+    }
+
+    #[test]
+    fn period_and_frequency() {
+        let rising = [0xff00, 0x0000, 0x00ff, 0x0200];
+        let mut meter = Meter::new(
+            Capture1 {
+                rising: &rising,
+                falling: &[],
+                resolution: Cell::new(0),
+                step: 1,
+            },
+            Nanoseconds(1_000),
+        );
+        assert_eq!(meter.capture.get_resolution(), Nanoseconds(1_000));
+
+        // 256, 255 and 257 us
+        assert_eq!(meter.period(true, 3), Ok(Nanoseconds(256_000)));
+        assert_eq!(meter.period(true, 1), Err(Error::Capture(())));
+
+        meter.capture.rising = &rising;
+        assert_eq!(meter.frequency(true, 3), Ok(Hertz(3_906)));
+    }
+
+    #[test]
+    fn waveform() {
+        let rising = [0xfff0, 0x0010, 0x0030];
+        let falling = [0x0000, 0x0018];
+        let mut meter = Meter::new(
+            Capture1 {
+                rising: &rising,
+                falling: &falling,
+                resolution: Cell::new(0),
+                step: 1,
+            },
+            Nanoseconds(500),
+        );
+
+        let waveform = meter.waveform(true, false, 2).unwrap();
+        assert_eq!(waveform.period(), Nanoseconds(16_000));
+        assert_eq!(waveform.pulse_width(), Nanoseconds(6_000));
+        assert_eq!(waveform.frequency(), Hertz(62_500));
+        assert_eq!(waveform.duty_cycle(100), 37);
+        assert_eq!(waveform.duty_cycle(1_000), 375);
+    }
+
+    #[test]
+    fn rounded_resolution() {
+        let rising = [0, 250];
+        let mut meter = Meter::new(
+            Capture1 {
+                rising: &rising,
+                falling: &[],
+                resolution: Cell::new(0),
+                step: 512,
+            },
+            Nanoseconds(1_000),
+        );
+        assert_eq!(meter.capture.get_resolution(), Nanoseconds(1_024));
+
+        assert_eq!(meter.period(true, 1), Ok(Nanoseconds(256_000)));
+    }
+
+    #[test]
+    fn overflow() {
+        let rising = [0, 0xffff];
+        let mut meter = Meter::new(
+            Capture1 {
+                rising: &rising,
+                falling: &[],
+                resolution: Cell::new(0),
+                step: 1,
+            },
+            Nanoseconds(100_000),
+        );
+
+        assert_eq!(meter.period(true, 1), Err(Error::Overflow));
+    }
+
+    #[test]
+    fn frequency_rounding() {
+        assert_eq!(frequency::<()>(1, 3), Ok(Hertz(333_333_333)));
+        assert_eq!(frequency::<()>(2, 3), Ok(Hertz(666_666_667)));
+        assert_eq!(frequency::<()>(u32::MAX, u64::MAX), Ok(Hertz(0)));
+        assert_eq!(frequency::<()>(5, 1), Err(Error::Overflow));
+        assert_eq!(frequency::<()>(1, 0), Err(Error::Overflow));
+    }
+}
@@ -1,7 +1,14 @@
 //! Implementation of `core::fmt::Write` for the HAL's `serial::Write`.
 //!
 //! TODO write example of usage
+//!
+//! The implementation for `serial::Write` trait objects ignores write errors. Use the [`Writer`]
+//! adapter to find out whether the formatted output actually made it to the serial interface.
+//!
+//! [`Writer`]: struct.Writer.html
 use core::fmt::{Result, Write};
+#[cfg(feature = "unproven")]
+use core::{fmt, marker::PhantomData};
 
 impl<Word, Error> Write for ::serial::Write<Word, Error=Error>
 where
@@ -15,3 +22,181 @@ where
         Ok(())
     }
 }
+
+/// `core::fmt::Write` adapter for a serial interface that doesn't ignore errors
+///
+/// When the serial interface fails the write is aborted with `fmt::Error` and the serial error is
+/// kept so it can be inspected with [`error`] or [`take_error`].
+///
+/// *This struct is available if embedded-hal is built with the `"unproven"` feature.*
+///
+/// [`error`]: #method.error
+/// [`take_error`]: #method.take_error
+///
+/// # Examples
+///
+/// ```
+/// extern crate embedded_hal as hal;
+///
+/// use std::fmt::Write;
+/// use hal::fmt::Writer;
+///
+/// fn main() {
+///     let serial: Serial1 = {
+///         // ..
+/// #       Serial1
+///     };
+///
+///     let mut writer = Writer::new(serial);
+///     writer.set_crlf(true);
+///
+///     if writeln!(writer, "temperature: {}", 21).is_err() {
+///         let error = writer.take_error();
+///         // omitted: handle the serial error
+///     }
+/// }
+///
+/// # extern crate nb;
+/// # struct Serial1;
+/// # impl hal::serial::Write<u8> for Serial1 {
+/// #     type Error = ();
+/// #     fn write(&mut self, _: u8) -> nb::Result<(), ()> { Ok(()) }
+/// #     fn flush(&mut self) -> nb::Result<(), ()> { Ok(()) }
+/// # }
+/// ```
+#[cfg(feature = "unproven")]
+pub struct Writer<S, Word = u8>
+where
+    S: ::serial::Write<Word>,
+{
+    serial: S,
+    error: Option<S::Error>,
+    crlf: bool,
+    _word: PhantomData<Word>,
+}
+
+#[cfg(feature = "unproven")]
+impl<S, Word> Writer<S, Word>
+where
+    S: ::serial::Write<Word>,
+    Word: From<u8>,
+{
+    /// Wraps `serial`
+    ///
+    /// Newline translation is disabled.
+    pub fn new(serial: S) -> Self {
+        Writer {
+            serial,
+            error: None,
+            crlf: false,
+            _word: PhantomData,
+        }
+    }
+
+    /// Enables or disables translating `\n` into `\r\n`
+    pub fn set_crlf(&mut self, enabled: bool) {
+        self.crlf = enabled;
+    }
+
+    /// Returns the error that aborted the last failed write, if any
+    pub fn error(&self) -> Option<&S::Error> {
+        self.error.as_ref()
+    }
+
+    /// Takes the error that aborted the last failed write, if any
+    pub fn take_error(&mut self) -> Option<S::Error> {
+        self.error.take()
+    }
+
+    /// Releases the serial interface
+    pub fn free(self) -> S {
+        self.serial
+    }
+
+    fn write_byte(&mut self, byte: u8) -> Result {
+        block!(self.serial.write(Word::from(byte))).map_err(|e| {
+            self.error = Some(e);
+            fmt::Error
+        })
+    }
+}
+
+#[cfg(feature = "unproven")]
+impl<S, Word> Write for Writer<S, Word>
+where
+    S: ::serial::Write<Word>,
+    Word: From<u8>,
+{
+    fn write_str(&mut self, s: &str) -> Result {
+        for &byte in s.as_bytes() {
+            if self.crlf && byte == b'\n' {
+                self.write_byte(b'\r')?;
+            }
+            self.write_byte(byte)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "unproven"))]
+mod tests {
+    use super::*;
+
+    use nb;
+
+    /// Serial interface that fails after accepting `capacity` bytes
+    struct Serial {
+        sent: [u8; 16],
+        len: usize,
+        capacity: usize,
+    }
+
+    impl Serial {
+        fn new(capacity: usize) -> Self {
+            Serial {
+                sent: [0; 16],
+                len: 0,
+                capacity,
+            }
+        }
+    }
+
+    impl ::serial::Write<u8> for Serial {
+        type Error = usize;
+
+        fn write(&mut self, word: u8) -> nb::Result<(), usize> {
+            if self.len == self.capacity {
+                return Err(nb::Error::Other(self.len));
+            }
+            self.sent[self.len] = word;
+            self.len += 1;
+            Ok(())
+        }
+
+        fn flush(&mut self) -> nb::Result<(), usize> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn crlf() {
+        let mut writer = Writer::new(Serial::new(16));
+        writer.write_str("a\nb\n").unwrap();
+        writer.set_crlf(true);
+        writer.write_str("c\n").unwrap();
+
+        let serial = writer.free();
+        assert_eq!(&serial.sent[..serial.len], b"a\nb\nc\r\n");
+    }
+
+    #[test]
+    fn propagates_errors() {
+        let mut writer = Writer::new(Serial::new(4));
+
+        assert_eq!(write!(writer, "{}", 123456), Err(fmt::Error));
+        assert_eq!(writer.error(), Some(&4));
+        assert_eq!(writer.take_error(), Some(4));
+        assert_eq!(writer.error(), None);
+    }
+}
@@ -192,6 +192,11 @@
 //! with `futures` or with the `await` operator using the [`block!`],
 //! [`try_nb!`] and [`await!`] macros respectively.
 //!
+//! For `async` / `.await` on an executor, the [`asynch`] module provides `core::future` based
+//! counterparts of some of the traits, along with adapters for the `nb` based ones. *That module is
+//! available if embedded-hal is built with the `"unproven"` feature.*
+//!
+//! [`asynch`]: asynch/index.html
 //! [`block!`]: https://docs.rs/nb/0.1.0/nb/macro.block.html
 //! [`try_nb!`]: https://docs.rs/nb/0.1.0/nb/index.html#how-to-use-this-crate
 //! [`await!`]: https://docs.rs/nb/0.1.0/nb/index.html#how-to-use-this-crate
@@ -687,15 +692,25 @@
 
 #[macro_use]
 extern crate nb;
+#[cfg(feature = "std")]
+extern crate std;
 extern crate void;
 
 pub mod adc;
+#[cfg(feature = "unproven")]
+pub mod asynch;
 pub mod blocking;
+#[cfg(feature = "unproven")]
+pub mod capture;
 pub mod digital;
 pub mod fmt;
 pub mod prelude;
+#[cfg(feature = "unproven")]
+pub mod pwm;
 pub mod serial;
 pub mod spi;
+#[cfg(feature = "unproven")]
+pub mod time;
 pub mod timer;
 pub mod watchdog;
 
@@ -703,6 +718,9 @@ pub mod watchdog;
 ///
 /// *This trait is available if embedded-hal is built with the `"unproven"` feature.*
 ///
+/// See the [`capture`](capture/index.html) module for ready-made period, frequency and duty cycle
+/// measurements.
+///
 /// # Examples
 ///
 /// You can use this interface to measure the period of (quasi) periodic signals
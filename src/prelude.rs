@@ -12,7 +12,15 @@ pub use blocking::i2c::{
     WriteRead as _embedded_hal_blocking_i2c_WriteRead,
 };
 #[cfg(feature = "unproven")]
+pub use blocking::i2c::Error as _embedded_hal_blocking_i2c_Error;
+#[cfg(feature = "unproven")]
+pub use blocking::i2c::recover::Recover as _embedded_hal_blocking_i2c_recover_Recover;
+#[cfg(feature = "unproven")]
+pub use blocking::register::RegisterInterface as _embedded_hal_blocking_register_RegisterInterface;
+#[cfg(feature = "unproven")]
 pub use blocking::rng::Read as _embedded_hal_blocking_rng_Read;
+#[cfg(feature = "unproven")]
+pub use blocking::serial::Read as _embedded_hal_blocking_serial_Read;
 pub use blocking::serial::Write as _embedded_hal_blocking_serial_Write;
 pub use blocking::spi::{
     Transfer as _embedded_hal_blocking_spi_Transfer, Write as _embedded_hal_blocking_spi_Write,
@@ -25,11 +33,31 @@ pub use digital::OutputPin as _embedded_hal_digital_OutputPin;
 #[cfg(feature = "unproven")]
 #[allow(deprecated)]
 pub use digital::ToggleableOutputPin as _embedded_hal_digital_ToggleableOutputPin;
+#[cfg(feature = "unproven")]
+pub use serial::framing::FrameDecoder as _embedded_hal_serial_framing_FrameDecoder;
+#[cfg(feature = "unproven")]
+pub use serial::Configure as _embedded_hal_serial_Configure;
+#[cfg(feature = "unproven")]
+pub use serial::Error as _embedded_hal_serial_Error;
+#[cfg(feature = "unproven")]
+pub use serial::LineEvents as _embedded_hal_serial_LineEvents;
 pub use serial::Read as _embedded_hal_serial_Read;
+#[cfg(feature = "unproven")]
+pub use serial::SendBreak as _embedded_hal_serial_SendBreak;
 pub use serial::Write as _embedded_hal_serial_Write;
+#[cfg(feature = "unproven")]
+pub use serial::WriteBuffer as _embedded_hal_serial_WriteBuffer;
 pub use spi::FullDuplex as _embedded_hal_spi_FullDuplex;
 pub use timer::CountDown as _embedded_hal_timer_CountDown;
 #[cfg(feature = "unproven")]
+pub use timer::Monotonic as _embedded_hal_timer_Monotonic;
+#[cfg(feature = "unproven")]
+pub use timer::Pause as _embedded_hal_timer_Pause;
+#[cfg(feature = "unproven")]
+pub use timer::Remaining as _embedded_hal_timer_Remaining;
+#[cfg(feature = "unproven")]
+pub use timer::SetPeriod as _embedded_hal_timer_SetPeriod;
+#[cfg(feature = "unproven")]
 pub use watchdog::Watchdog as _embedded_hal_watchdog_Watchdog;
 #[cfg(feature = "unproven")]
 pub use watchdog::WatchdogDisable as _embedded_hal_watchdog_WatchdogDisable;
@@ -0,0 +1,189 @@
+//! Complementary outputs, dead time and break input
+//!
+//! Half bridges, as found in motor drivers and power converters, are driven by a pair of
+//! complementary outputs: the high-side output follows the PWM signal and the low-side output its
+//! inverse. Both switches must never conduct at the same time, so a *dead time* is inserted
+//! between one output turning off and the other one turning on, and a *break* input shuts the
+//! outputs down in hardware on a fault.
+//!
+//! # Examples
+//!
+//! ```
+//! extern crate embedded_hal as hal;
+//!
+//! use hal::pwm::complementary::{
+//!     Alignment, BreakInput, ComplementaryPwmPin, DeadTime, Polarity, SetAlignment,
+//! };
+//! use hal::pwm::v2::PwmPin;
+//! use hal::time::Nanoseconds;
+//!
+//! /// Generic half bridge driver
+//! fn start<T, P>(timer: &mut T, phase: &mut P) -> Result<(), ()>
+//! where
+//!     T: DeadTime<Error = ()> + BreakInput<Error = ()> + SetAlignment<Error = ()>,
+//!     P: ComplementaryPwmPin<Error = ()>,
+//!     P::Duty: Into<u32> + core::convert::TryFrom<u32>,
+//! {
+//!     // the MOSFETs need 250 ns to turn off
+//!     let dead_time = timer.set_dead_time(Nanoseconds(250))?;
+//!     assert!(dead_time.0 >= 250);
+//!
+//!     timer.set_alignment(Alignment::Center)?;
+//!     // the over-current comparator pulls the break input low
+//!     timer.enable_break(Polarity::ActiveLow)?;
+//!
+//!     phase.set_duty_percent(50)?;
+//!     phase.enable()?;
+//!     phase.enable_complementary()
+//! }
+//!
+//! fn main() {
+//!     let mut timer: Tim1 = {
+//!         // ..
+//! #       Tim1
+//!     };
+//!     let mut phase_a: Tim1Ch1 = {
+//!         // ..
+//! #       Tim1Ch1
+//!     };
+//!
+//!     start(&mut timer, &mut phase_a).unwrap();
+//!
+//!     // later on, in the main loop
+//!     if timer.is_break_active().unwrap() {
+//!         // the outputs were shut down; recover once the fault is gone
+//!         timer.clear_break().unwrap();
+//!     }
+//! }
+//!
+//! # struct Tim1;
+//! # impl DeadTime for Tim1 {
+//! #     type Error = ();
+//! #     fn get_dead_time(&self) -> Result<Nanoseconds, ()> { Ok(Nanoseconds(278)) }
+//! #     fn set_dead_time(&mut self, _: Nanoseconds) -> Result<Nanoseconds, ()> {
+//! #         Ok(Nanoseconds(278))
+//! #     }
+//! # }
+//! # impl BreakInput for Tim1 {
+//! #     type Error = ();
+//! #     fn enable_break(&mut self, _: Polarity) -> Result<(), ()> { Ok(()) }
+//! #     fn disable_break(&mut self) -> Result<(), ()> { Ok(()) }
+//! #     fn is_break_active(&self) -> Result<bool, ()> { Ok(false) }
+//! #     fn clear_break(&mut self) -> Result<(), ()> { Ok(()) }
+//! # }
+//! # impl SetAlignment for Tim1 {
+//! #     type Error = ();
+//! #     fn get_alignment(&self) -> Result<Alignment, ()> { Ok(Alignment::Center) }
+//! #     fn set_alignment(&mut self, _: Alignment) -> Result<(), ()> { Ok(()) }
+//! # }
+//! # struct Tim1Ch1;
+//! # impl PwmPin for Tim1Ch1 {
+//! #     type Error = ();
+//! #     type Duty = u16;
+//! #     fn disable(&mut self) -> Result<(), ()> { Ok(()) }
+//! #     fn enable(&mut self) -> Result<(), ()> { Ok(()) }
+//! #     fn get_duty(&self) -> Result<u16, ()> { Ok(0) }
+//! #     fn get_max_duty(&self) -> Result<u16, ()> { Ok(1_000) }
+//! #     fn set_duty(&mut self, _: u16) -> Result<(), ()> { Ok(()) }
+//! # }
+//! # impl ComplementaryPwmPin for Tim1Ch1 {
+//! #     fn disable_complementary(&mut self) -> Result<(), ()> { Ok(()) }
+//! #     fn enable_complementary(&mut self) -> Result<(), ()> { Ok(()) }
+//! # }
+//! ```
+
+use super::v2::PwmPin;
+use time::Nanoseconds;
+
+/// A PWM channel with a main (high-side) and a complementary (low-side) output
+///
+/// The `PwmPin` methods act on the main output; the complementary output is the inverse of the
+/// main output, with the dead time of the timer inserted around every transition.
+pub trait ComplementaryPwmPin: PwmPin {
+    /// Disables the complementary output
+    fn disable_complementary(&mut self) -> Result<(), Self::Error>;
+
+    /// Enables the complementary output
+    fn enable_complementary(&mut self) -> Result<(), Self::Error>;
+}
+
+/// Dead time inserted between complementary outputs
+pub trait DeadTime {
+    /// Error type
+    type Error;
+
+    /// Returns the current dead time
+    fn get_dead_time(&self) -> Result<Nanoseconds, Self::Error>;
+
+    /// Sets a new dead time and returns the dead time that was actually set
+    ///
+    /// # Contract
+    ///
+    /// - The dead time that was set MUST NOT be shorter than `dead_time`; implementations round up
+    ///   to the resolution of the hardware.
+    /// - If `dead_time` exceeds the longest dead time the hardware supports an error MUST be
+    ///   returned and the dead time MUST be left unchanged.
+    fn set_dead_time(&mut self, dead_time: Nanoseconds) -> Result<Nanoseconds, Self::Error>;
+}
+
+/// Active level of a break input
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Polarity {
+    /// The break is triggered when the input is low
+    ActiveLow,
+    /// The break is triggered when the input is high
+    ActiveHigh,
+}
+
+/// Emergency break (fault) input
+///
+/// When the break input becomes active the hardware immediately drives all the PWM outputs of the
+/// timer to their inactive (safe) state, without software intervention. The outputs stay off
+/// until the break is cleared.
+pub trait BreakInput {
+    /// Error type
+    type Error;
+
+    /// Disables the break input
+    fn disable_break(&mut self) -> Result<(), Self::Error>;
+
+    /// Enables the break input, triggering on the given `polarity`
+    fn enable_break(&mut self, polarity: Polarity) -> Result<(), Self::Error>;
+
+    /// Returns `true` if a break occurred and the outputs have been shut down
+    fn is_break_active(&self) -> Result<bool, Self::Error>;
+
+    /// Enables the outputs again after a break
+    ///
+    /// # Errors
+    ///
+    /// An error will be returned if the break input is still active.
+    fn clear_break(&mut self) -> Result<(), Self::Error>;
+}
+
+/// Alignment of the PWM signal within its period
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Alignment {
+    /// The outputs turn on at the start of the period and off when the duty cycle elapses
+    Edge,
+    /// The pulses are centered within the period; this lowers the harmonics of multi-phase
+    /// drives as the outputs of different channels don't switch at the same time
+    Center,
+}
+
+/// PWM timer that can switch between edge-aligned and center-aligned modes
+pub trait SetAlignment {
+    /// Error type
+    type Error;
+
+    /// Returns the current alignment
+    fn get_alignment(&self) -> Result<Alignment, Self::Error>;
+
+    /// Sets a new alignment
+    ///
+    /// # Contract
+    ///
+    /// - The PWM period MUST be kept, i.e. implementations adjust the counter configuration as
+    ///   needed. The value returned by `get_max_duty` may change.
+    fn set_alignment(&mut self, alignment: Alignment) -> Result<(), Self::Error>;
+}
@@ -0,0 +1,16 @@
+//! Pulse Width Modulation
+//!
+//! The original, infallible `Pwm` and `PwmPin` traits live at the root of the crate. This module
+//! holds their fallible successors.
+//!
+//! *This module is available if embedded-hal is built with the `"unproven"` feature.*
+
+// Complementary outputs for half bridges
+pub mod complementary;
+
+// New / fallible traits
+pub mod v2;
+
+// v1 -> v2 compatibility shims
+// These are implicit over implementations of the root `Pwm` / `PwmPin` traits
+pub mod v2_compat;
@@ -0,0 +1,252 @@
+//! Pulse Width Modulation
+//!
+//! Version 2 / fallible traits. Infallible implementations should set Error to `!`.
+
+use core::convert::TryFrom;
+
+/// A single PWM channel / pin
+///
+/// # Examples
+///
+/// ```
+/// extern crate embedded_hal as hal;
+///
+/// use hal::pwm::v2::PwmPin;
+///
+/// fn main() {
+///     let mut led: Pwm1Ch1 = {
+///         // ..
+/// #       Pwm1Ch1(0)
+///     };
+///
+///     led.enable().unwrap();
+///     led.set_duty_percent(25).unwrap();
+///     assert_eq!(led.get_duty(), Ok(250));
+/// }
+///
+/// # struct Pwm1Ch1(u16);
+/// # impl PwmPin for Pwm1Ch1 {
+/// #     type Error = ();
+/// #     type Duty = u16;
+/// #     fn disable(&mut self) -> Result<(), ()> { Ok(()) }
+/// #     fn enable(&mut self) -> Result<(), ()> { Ok(()) }
+/// #     fn get_duty(&self) -> Result<u16, ()> { Ok(self.0) }
+/// #     fn get_max_duty(&self) -> Result<u16, ()> { Ok(1_000) }
+/// #     fn set_duty(&mut self, duty: u16) -> Result<(), ()> { self.0 = duty; Ok(()) }
+/// # }
+/// ```
+pub trait PwmPin {
+    /// Error type
+    type Error;
+
+    /// Type for the `duty` methods
+    ///
+    /// The implementer is free to choose a float / percentage representation
+    /// (e.g. `0.0 .. 1.0`) or an integer representation (e.g. `0 .. 65535`)
+    type Duty;
+
+    /// Disables a PWM `channel`
+    fn disable(&mut self) -> Result<(), Self::Error>;
+
+    /// Enables a PWM `channel`
+    fn enable(&mut self) -> Result<(), Self::Error>;
+
+    /// Returns the current duty cycle
+    fn get_duty(&self) -> Result<Self::Duty, Self::Error>;
+
+    /// Returns the maximum duty cycle value
+    fn get_max_duty(&self) -> Result<Self::Duty, Self::Error>;
+
+    /// Sets a new duty cycle
+    fn set_duty(&mut self, duty: Self::Duty) -> Result<(), Self::Error>;
+
+    /// Sets the duty cycle to `num / den` of the maximum duty cycle, rounding down
+    ///
+    /// Ratios above one are clamped to the maximum duty cycle, as are duty cycles that can't be
+    /// converted back into `Duty`.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `den` is zero.
+    fn set_duty_ratio(&mut self, num: u32, den: u32) -> Result<(), Self::Error>
+    where
+        Self::Duty: Into<u32> + TryFrom<u32>,
+    {
+        let duty = match Self::Duty::try_from(ratio(self.get_max_duty()?.into(), num, den)) {
+            Ok(duty) => duty,
+            Err(_) => self.get_max_duty()?,
+        };
+
+        self.set_duty(duty)
+    }
+
+    /// Sets the duty cycle to `percent` percent of the maximum duty cycle, rounding down
+    ///
+    /// Percentages above 100 are clamped to the maximum duty cycle.
+    fn set_duty_percent(&mut self, percent: u8) -> Result<(), Self::Error>
+    where
+        Self::Duty: Into<u32> + TryFrom<u32>,
+    {
+        self.set_duty_ratio(u32::from(percent), 100)
+    }
+}
+
+/// A PWM channel / pin whose period can be changed independently of other channels
+pub trait PwmPinPeriod: PwmPin {
+    /// A time unit that can be converted into a human time unit (e.g. seconds)
+    ///
+    /// This can be a frequency (e.g. `time::Hertz`) or a duration (e.g. `time::Microseconds`).
+    type Time;
+
+    /// Returns the current PWM period
+    fn get_period(&self) -> Result<Self::Time, Self::Error>;
+
+    /// Sets a new PWM period
+    ///
+    /// Changing the period may change the value returned by `get_max_duty`, so set the duty cycle
+    /// again afterwards.
+    fn set_period<P>(&mut self, period: P) -> Result<(), Self::Error>
+    where
+        P: Into<Self::Time>;
+}
+
+/// Pulse Width Modulation timer
+///
+/// The timer-wide part of a PWM peripheral whose channels share a single period; see
+/// `PwmChannels` to drive its channels and `PwmPin` for independent channels.
+pub trait Pwm {
+    /// Error type
+    type Error;
+
+    /// A time unit that can be converted into a human time unit (e.g. seconds)
+    type Time;
+
+    /// Type for the `duty` methods
+    ///
+    /// The implementer is free to choose a float / percentage representation
+    /// (e.g. `0.0 .. 1.0`) or an integer representation (e.g. `0 .. 65535`)
+    type Duty;
+
+    /// Returns the current PWM period
+    fn get_period(&self) -> Result<Self::Time, Self::Error>;
+
+    /// Returns the maximum duty cycle value of all channels
+    fn get_max_duty(&self) -> Result<Self::Duty, Self::Error>;
+
+    /// Sets a new PWM period for all channels
+    ///
+    /// Changing the period may change the value returned by `get_max_duty`, so set the duty cycles
+    /// again afterwards.
+    fn set_period<P>(&mut self, period: P) -> Result<(), Self::Error>
+    where
+        P: Into<Self::Time>;
+}
+
+/// The channels of a `Pwm` timer
+///
+/// # Examples
+///
+/// ```
+/// extern crate embedded_hal as hal;
+///
+/// use hal::pwm::v2::{Pwm, PwmChannels};
+///
+/// fn main() {
+///     let mut pwm: Pwm1 = {
+///         // ..
+/// #       Pwm1([0; 2])
+///     };
+///
+///     let max = pwm.get_max_duty().unwrap();
+///     pwm.set_duty(Channel::_1, max / 2).unwrap();
+///     pwm.set_duty(Channel::_2, max / 4).unwrap();
+///     pwm.enable(Channel::_1).unwrap();
+///     pwm.enable(Channel::_2).unwrap();
+///     assert_eq!(pwm.get_duty(Channel::_2), Ok(250));
+/// }
+///
+/// # #[derive(Clone, Copy)]
+/// # enum Channel { _1, _2 }
+/// # struct Pwm1([u16; 2]);
+/// # impl Pwm for Pwm1 {
+/// #     type Error = ();
+/// #     type Time = u32;
+/// #     type Duty = u16;
+/// #     fn get_period(&self) -> Result<u32, ()> { Ok(1_000) }
+/// #     fn get_max_duty(&self) -> Result<u16, ()> { Ok(1_000) }
+/// #     fn set_period<P>(&mut self, _: P) -> Result<(), ()> where P: Into<u32> { Ok(()) }
+/// # }
+/// # impl PwmChannels for Pwm1 {
+/// #     type Channel = Channel;
+/// #     fn disable(&mut self, _: Channel) -> Result<(), ()> { Ok(()) }
+/// #     fn enable(&mut self, _: Channel) -> Result<(), ()> { Ok(()) }
+/// #     fn get_duty(&self, c: Channel) -> Result<u16, ()> { Ok(self.0[c as usize]) }
+/// #     fn set_duty(&mut self, c: Channel, duty: u16) -> Result<(), ()> {
+/// #         self.0[c as usize] = duty;
+/// #         Ok(())
+/// #     }
+/// # }
+/// ```
+pub trait PwmChannels: Pwm {
+    /// Enumeration of channels that can be used with this `Pwm` interface
+    ///
+    /// If your `Pwm` interface has no channels you can use the type `()`
+    /// here
+    type Channel;
+
+    /// Disables a PWM `channel`
+    fn disable(&mut self, channel: Self::Channel) -> Result<(), Self::Error>;
+
+    /// Enables a PWM `channel`
+    fn enable(&mut self, channel: Self::Channel) -> Result<(), Self::Error>;
+
+    /// Returns the current duty cycle of `channel`
+    fn get_duty(&self, channel: Self::Channel) -> Result<Self::Duty, Self::Error>;
+
+    /// Sets a new duty cycle for `channel`
+    fn set_duty(&mut self, channel: Self::Channel, duty: Self::Duty) -> Result<(), Self::Error>;
+
+    /// Sets the duty cycle of `channel` to `num / den` of the maximum duty cycle, rounding down
+    ///
+    /// Ratios above one are clamped to the maximum duty cycle, as are duty cycles that can't be
+    /// converted back into `Duty`.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `den` is zero.
+    fn set_duty_ratio(
+        &mut self,
+        channel: Self::Channel,
+        num: u32,
+        den: u32,
+    ) -> Result<(), Self::Error>
+    where
+        Self::Duty: Into<u32> + TryFrom<u32>,
+    {
+        let duty = match Self::Duty::try_from(ratio(self.get_max_duty()?.into(), num, den)) {
+            Ok(duty) => duty,
+            Err(_) => self.get_max_duty()?,
+        };
+
+        self.set_duty(channel, duty)
+    }
+
+    /// Sets the duty cycle of `channel` to `percent` percent of the maximum duty cycle, rounding
+    /// down
+    ///
+    /// Percentages above 100 are clamped to the maximum duty cycle.
+    fn set_duty_percent(&mut self, channel: Self::Channel, percent: u8) -> Result<(), Self::Error>
+    where
+        Self::Duty: Into<u32> + TryFrom<u32>,
+    {
+        self.set_duty_ratio(channel, u32::from(percent), 100)
+    }
+}
+
+// `num / den` of `max`, rounding down and clamping ratios above one
+fn ratio(max: u32, num: u32, den: u32) -> u32 {
+    assert!(den > 0);
+
+    let num = if num < den { num } else { den };
+    (u64::from(max) * u64::from(num) / u64::from(den)) as u32
+}
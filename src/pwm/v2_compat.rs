@@ -0,0 +1,284 @@
+//! v2 compatibility shims
+//!
+//! This module adds implicit forward support to the root `Pwm` / `PwmPin` traits,
+//! allowing their implementations to be directly used with v2 consumers.
+
+use super::v2;
+
+/// Implementation of fallible `v2::PwmPin` for the root `PwmPin` trait
+impl<T> v2::PwmPin for T
+where
+    T: ::PwmPin,
+{
+    // TODO: update to ! when never_type is stabilized
+    type Error = ();
+    type Duty = T::Duty;
+
+    fn disable(&mut self) -> Result<(), ()> {
+        ::PwmPin::disable(self);
+        Ok(())
+    }
+
+    fn enable(&mut self) -> Result<(), ()> {
+        ::PwmPin::enable(self);
+        Ok(())
+    }
+
+    fn get_duty(&self) -> Result<T::Duty, ()> {
+        Ok(::PwmPin::get_duty(self))
+    }
+
+    fn get_max_duty(&self) -> Result<T::Duty, ()> {
+        Ok(::PwmPin::get_max_duty(self))
+    }
+
+    fn set_duty(&mut self, duty: T::Duty) -> Result<(), ()> {
+        ::PwmPin::set_duty(self, duty);
+        Ok(())
+    }
+}
+
+/// Implementation of fallible `v2::Pwm` for the root `Pwm` trait
+impl<T> v2::Pwm for T
+where
+    T: ::Pwm,
+{
+    // TODO: update to ! when never_type is stabilized
+    type Error = ();
+    type Time = T::Time;
+    type Duty = T::Duty;
+
+    fn get_period(&self) -> Result<T::Time, ()> {
+        Ok(::Pwm::get_period(self))
+    }
+
+    fn get_max_duty(&self) -> Result<T::Duty, ()> {
+        Ok(::Pwm::get_max_duty(self))
+    }
+
+    fn set_period<P>(&mut self, period: P) -> Result<(), ()>
+    where
+        P: Into<T::Time>,
+    {
+        ::Pwm::set_period(self, period);
+        Ok(())
+    }
+}
+
+/// Implementation of fallible `v2::PwmChannels` for the root `Pwm` trait
+impl<T> v2::PwmChannels for T
+where
+    T: ::Pwm,
+{
+    type Channel = T::Channel;
+
+    fn disable(&mut self, channel: T::Channel) -> Result<(), ()> {
+        ::Pwm::disable(self, channel);
+        Ok(())
+    }
+
+    fn enable(&mut self, channel: T::Channel) -> Result<(), ()> {
+        ::Pwm::enable(self, channel);
+        Ok(())
+    }
+
+    fn get_duty(&self, channel: T::Channel) -> Result<T::Duty, ()> {
+        Ok(::Pwm::get_duty(self, channel))
+    }
+
+    fn set_duty(&mut self, channel: T::Channel, duty: T::Duty) -> Result<(), ()> {
+        ::Pwm::set_duty(self, channel, duty);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::TryFrom;
+
+    use pwm::v2;
+
+    struct OldPwmPinImpl {
+        duty: u16,
+        enabled: bool,
+    }
+
+    impl ::PwmPin for OldPwmPinImpl {
+        type Duty = u16;
+
+        fn disable(&mut self) {
+            self.enabled = false;
+        }
+
+        fn enable(&mut self) {
+            self.enabled = true;
+        }
+
+        fn get_duty(&self) -> u16 {
+            self.duty
+        }
+
+        fn get_max_duty(&self) -> u16 {
+            1_000
+        }
+
+        fn set_duty(&mut self, duty: u16) {
+            self.duty = duty;
+        }
+    }
+
+    #[test]
+    fn v2_pwm_pin_implicit() {
+        let mut pin = OldPwmPinImpl {
+            duty: 0,
+            enabled: false,
+        };
+
+        v2::PwmPin::enable(&mut pin).unwrap();
+        assert!(pin.enabled);
+
+        v2::PwmPin::set_duty(&mut pin, 10).unwrap();
+        assert_eq!(v2::PwmPin::get_duty(&pin), Ok(10));
+    }
+
+    #[test]
+    fn duty_ratio() {
+        let mut pin = OldPwmPinImpl {
+            duty: 0,
+            enabled: false,
+        };
+
+        v2::PwmPin::set_duty_percent(&mut pin, 33).unwrap();
+        assert_eq!(pin.duty, 330);
+        v2::PwmPin::set_duty_ratio(&mut pin, 2, 3).unwrap();
+        assert_eq!(pin.duty, 666);
+        v2::PwmPin::set_duty_ratio(&mut pin, 5, 4).unwrap();
+        assert_eq!(pin.duty, 1_000);
+        v2::PwmPin::set_duty_percent(&mut pin, 0).unwrap();
+        assert_eq!(pin.duty, 0);
+    }
+
+    struct OldPwmImpl {
+        duty: [u16; 2],
+        period: u32,
+    }
+
+    impl ::Pwm for OldPwmImpl {
+        type Channel = usize;
+        type Time = u32;
+        type Duty = u16;
+
+        fn disable(&mut self, _: usize) {}
+
+        fn enable(&mut self, _: usize) {}
+
+        fn get_period(&self) -> u32 {
+            self.period
+        }
+
+        fn get_duty(&self, channel: usize) -> u16 {
+            self.duty[channel]
+        }
+
+        fn get_max_duty(&self) -> u16 {
+            1_000
+        }
+
+        fn set_duty(&mut self, channel: usize, duty: u16) {
+            self.duty[channel] = duty;
+        }
+
+        fn set_period<P>(&mut self, period: P)
+        where
+            P: Into<u32>,
+        {
+            self.period = period.into();
+        }
+    }
+
+    #[test]
+    fn v2_pwm_implicit() {
+        let mut pwm = OldPwmImpl {
+            duty: [0; 2],
+            period: 0,
+        };
+
+        v2::Pwm::set_period(&mut pwm, 20_000u32).unwrap();
+        assert_eq!(v2::Pwm::get_period(&pwm), Ok(20_000));
+        assert_eq!(v2::Pwm::get_max_duty(&pwm), Ok(1_000));
+
+        v2::PwmChannels::set_duty(&mut pwm, 1, 10).unwrap();
+        assert_eq!(v2::PwmChannels::get_duty(&pwm, 1), Ok(10));
+        assert_eq!(pwm.duty, [0, 10]);
+    }
+
+    #[test]
+    fn channel_duty_ratio() {
+        let mut pwm = OldPwmImpl {
+            duty: [0; 2],
+            period: 0,
+        };
+
+        v2::PwmChannels::set_duty_percent(&mut pwm, 0, 25).unwrap();
+        v2::PwmChannels::set_duty_ratio(&mut pwm, 1, 1, 3).unwrap();
+        assert_eq!(pwm.duty, [250, 333]);
+        v2::PwmChannels::set_duty_percent(&mut pwm, 1, 150).unwrap();
+        assert_eq!(pwm.duty, [250, 1_000]);
+    }
+
+    /// Duty cycle whose `TryFrom` rejects values above 500
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Duty(u32);
+
+    impl From<Duty> for u32 {
+        fn from(duty: Duty) -> u32 {
+            duty.0
+        }
+    }
+
+    impl TryFrom<u32> for Duty {
+        type Error = ();
+
+        fn try_from(duty: u32) -> Result<Duty, ()> {
+            if duty <= 500 {
+                Ok(Duty(duty))
+            } else {
+                Err(())
+            }
+        }
+    }
+
+    struct BuggyPin {
+        duty: Duty,
+    }
+
+    impl ::PwmPin for BuggyPin {
+        type Duty = Duty;
+
+        fn disable(&mut self) {}
+
+        fn enable(&mut self) {}
+
+        fn get_duty(&self) -> Duty {
+            self.duty
+        }
+
+        fn get_max_duty(&self) -> Duty {
+            Duty(1_000)
+        }
+
+        fn set_duty(&mut self, duty: Duty) {
+            self.duty = duty;
+        }
+    }
+
+    #[test]
+    fn duty_ratio_conversion_failure() {
+        let mut pin = BuggyPin { duty: Duty(0) };
+
+        v2::PwmPin::set_duty_percent(&mut pin, 40).unwrap();
+        assert_eq!(pin.duty, Duty(400));
+        v2::PwmPin::set_duty_percent(&mut pin, 60).unwrap();
+        assert_eq!(pin.duty, Duty(1_000));
+    }
+}
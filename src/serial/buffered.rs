@@ -0,0 +1,591 @@
+//! Interrupt driven, buffered serial interfaces
+//!
+//! Polling a serial interface from the main loop drops words as soon as the loop takes longer
+//! than the hardware FIFO lasts, which at high baud rates is only a few microseconds. The types in
+//! this module move words between the serial interface and a [`RingBuffer`] from an interrupt
+//! handler instead:
+//!
+//! - [`BufferedRx`] is polled from the receive interrupt handler and pushes every received word
+//!   into the ring buffer. The application reads them back through the buffer's [`Consumer`],
+//!   which implements `serial::Read` and the blocking read traits.
+//! - [`BufferedTx`] is polled from the transmit interrupt handler and sends the words the
+//!   application pushed into the ring buffer through its [`Producer`], which implements
+//!   `serial::Write` and the blocking write traits.
+//!
+//! When the receive buffer is full incoming words are dropped and counted. The next read from the
+//! [`Consumer`] then reports an [`Overrun`] error.
+//!
+//! *This module is available if embedded-hal is built with the `"unproven"` feature.*
+//!
+//! [`RingBuffer`]: struct.RingBuffer.html
+//! [`BufferedRx`]: struct.BufferedRx.html
+//! [`BufferedTx`]: struct.BufferedTx.html
+//! [`Consumer`]: struct.Consumer.html
+//! [`Producer`]: struct.Producer.html
+//! [`Overrun`]: struct.Overrun.html
+//!
+//! ```
+//! extern crate embedded_hal as hal;
+//!
+//! use hal::prelude::*;
+//! use hal::serial::buffered::{BufferedRx, RingBuffer};
+//!
+//! fn main() {
+//!     let serial: Serial1 = {
+//!         // ..
+//! #       Serial1(b"OK\n")
+//!     };
+//!
+//!     let mut storage = [0; 64];
+//!     let mut buffer = RingBuffer::new(&mut storage);
+//!     let (producer, mut consumer) = buffer.split();
+//!
+//!     // omitted: move `rx` to the interrupt handler
+//!     let mut rx = BufferedRx::new(serial, producer);
+//!
+//!     // in the interrupt handler
+//!     rx.poll().unwrap();
+//!
+//!     // in the application
+//!     let mut line = [0; 32];
+//!     let n = consumer.bread_until(b'\n', &mut line).unwrap();
+//!     assert_eq!(&line[..n], b"OK\n");
+//! }
+//!
+//! # struct Serial1(&'static [u8]);
+//! # impl hal::serial::Read<u8> for Serial1 {
+//! #     type Error = ();
+//! #     fn read(&mut self) -> nb::Result<u8, ()> {
+//! #         let (&first, rest) = self.0.split_first().ok_or(nb::Error::WouldBlock)?;
+//! #         self.0 = rest;
+//! #         Ok(first)
+//! #     }
+//! # }
+//! # extern crate nb;
+//! ```
+
+use core::cell::Cell;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use nb;
+use void::Void;
+
+use serial::{ErrorKind, Read, Write};
+
+/// Lock-free single producer, single consumer queue of bytes
+///
+/// The bytes are kept in caller-provided storage, of which all but one byte can be used. The
+/// buffer is [`split`] into a [`Producer`] and a [`Consumer`] that are then handed to an interrupt
+/// handler and the application, respectively. Neither half ever blocks or disables interrupts;
+/// only atomic loads and stores are used, so this also works on cores without compare-and-swap
+/// instructions.
+///
+/// Both halves borrow the buffer, which in turn borrows the storage. To move a half into an
+/// interrupt handler, both the storage and the buffer must therefore be `'static`. Get them from
+/// a `singleton!` style macro, or from `static mut` items in `unsafe` code that ensures they're
+/// only borrowed once.
+///
+/// [`split`]: #method.split
+/// [`Producer`]: struct.Producer.html
+/// [`Consumer`]: struct.Consumer.html
+pub struct RingBuffer<'a> {
+    // index of the next byte that will be written; only written by the producer
+    head: AtomicUsize,
+    // index of the next byte that will be read; only written by the consumer
+    tail: AtomicUsize,
+    // number of bytes that were dropped; only written by the producer
+    overruns: AtomicUsize,
+    // number of bytes ever enqueued, wrapping; only written by the producer
+    written: AtomicUsize,
+    // value of `written` when a `BufferedTx` last finished sending; only written by the consumer
+    flushed: AtomicUsize,
+    buffer: &'a [Cell<u8>],
+}
+
+// NOTE(unsafe) the producer only writes to the free part of `buffer` and the consumer only reads
+// from the used part, and `split` hands out at most one of each
+unsafe impl<'a> Sync for RingBuffer<'a> {}
+
+impl<'a> RingBuffer<'a> {
+    /// Creates an empty ring buffer that keeps its bytes in `storage`
+    ///
+    /// # Panics
+    ///
+    /// If `storage` is shorter than 2 bytes
+    pub fn new(storage: &'a mut [u8]) -> Self {
+        assert!(
+            storage.len() >= 2,
+            "ring buffer needs at least 2 bytes of storage"
+        );
+
+        RingBuffer {
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            overruns: AtomicUsize::new(0),
+            written: AtomicUsize::new(0),
+            flushed: AtomicUsize::new(0),
+            buffer: Cell::from_mut(storage).as_slice_of_cells(),
+        }
+    }
+
+    /// Returns the maximum number of bytes the buffer can hold
+    pub fn capacity(&self) -> usize {
+        self.buffer.len() - 1
+    }
+
+    /// Splits the buffer into its producer and consumer halves
+    pub fn split<'r>(&'r mut self) -> (Producer<'r>, Consumer<'r>) {
+        let rb: &'r RingBuffer<'r> = self;
+
+        (
+            Producer { rb },
+            Consumer {
+                rb,
+                overruns: rb.overruns.load(Ordering::Relaxed),
+            },
+        )
+    }
+
+    fn len(&self) -> usize {
+        let head = self.head.load(Ordering::Acquire);
+        let tail = self.tail.load(Ordering::Acquire);
+
+        if head >= tail {
+            head - tail
+        } else {
+            self.buffer.len() - tail + head
+        }
+    }
+
+    fn next(&self, index: usize) -> usize {
+        if index + 1 == self.buffer.len() {
+            0
+        } else {
+            index + 1
+        }
+    }
+}
+
+/// Write half of a [`RingBuffer`](struct.RingBuffer.html)
+///
+/// `serial::Write::write` returns `WouldBlock` while the buffer is full and `flush` returns
+/// `WouldBlock` until a [`BufferedTx`](struct.BufferedTx.html) has sent every byte and flushed its
+/// serial interface.
+pub struct Producer<'a> {
+    rb: &'a RingBuffer<'a>,
+}
+
+impl<'a> Producer<'a> {
+    /// Appends `byte` to the buffer
+    ///
+    /// Returns `byte` back if the buffer is full.
+    pub fn enqueue(&mut self, byte: u8) -> Result<(), u8> {
+        let head = self.rb.head.load(Ordering::Relaxed);
+        let next = self.rb.next(head);
+
+        if next == self.rb.tail.load(Ordering::Acquire) {
+            return Err(byte);
+        }
+
+        // the consumer doesn't access the slot at `head` until `head` is advanced
+        self.rb.buffer[head].set(byte);
+        self.rb.head.store(next, Ordering::Release);
+
+        // this is the only writer, see `record_overrun`
+        let written = self.rb.written.load(Ordering::Relaxed);
+        self.rb
+            .written
+            .store(written.wrapping_add(1), Ordering::Release);
+
+        Ok(())
+    }
+
+    /// Returns the number of bytes in the buffer
+    pub fn len(&self) -> usize {
+        self.rb.len()
+    }
+
+    /// Returns `true` if the buffer is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if the buffer is full
+    pub fn is_full(&self) -> bool {
+        self.len() == self.rb.capacity()
+    }
+
+    fn record_overrun(&mut self) {
+        // load + store rather than `fetch_add`, which isn't available on all targets; this is
+        // the only writer
+        let overruns = self.rb.overruns.load(Ordering::Relaxed);
+        self.rb
+            .overruns
+            .store(overruns.wrapping_add(1), Ordering::Release);
+    }
+}
+
+impl<'a> Write<u8> for Producer<'a> {
+    type Error = Void;
+
+    fn write(&mut self, word: u8) -> nb::Result<(), Void> {
+        self.enqueue(word).map_err(|_| nb::Error::WouldBlock)
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Void> {
+        let written = self.rb.written.load(Ordering::Relaxed);
+        if self.rb.flushed.load(Ordering::Acquire) == written {
+            Ok(())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+}
+
+impl<'a> ::blocking::serial::write::Default<u8> for Producer<'a> {}
+
+/// Read half of a [`RingBuffer`](struct.RingBuffer.html)
+///
+/// `serial::Read::read` returns `WouldBlock` while the buffer is empty.
+pub struct Consumer<'a> {
+    rb: &'a RingBuffer<'a>,
+    // overrun count that has already been reported
+    overruns: usize,
+}
+
+impl<'a> Consumer<'a> {
+    /// Removes the oldest byte from the buffer
+    pub fn dequeue(&mut self) -> Option<u8> {
+        let tail = self.rb.tail.load(Ordering::Relaxed);
+
+        if tail == self.rb.head.load(Ordering::Acquire) {
+            return None;
+        }
+
+        // the producer doesn't access the slot at `tail` until `tail` is advanced
+        let byte = self.rb.buffer[tail].get();
+        self.rb.tail.store(self.rb.next(tail), Ordering::Release);
+
+        Some(byte)
+    }
+
+    /// Returns the number of bytes in the buffer
+    pub fn len(&self) -> usize {
+        self.rb.len()
+    }
+
+    /// Returns `true` if the buffer is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the total number of bytes that were dropped because the buffer was full
+    ///
+    /// The count wraps around on overflow.
+    pub fn overruns(&self) -> usize {
+        self.rb.overruns.load(Ordering::Acquire)
+    }
+}
+
+/// Words were dropped because the receive buffer was full
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Overrun {
+    /// Number of words dropped since the previous `Overrun` error
+    pub dropped: usize,
+}
+
+impl ::serial::Error for Overrun {
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Overrun
+    }
+}
+
+impl<'a> Read<u8> for Consumer<'a> {
+    type Error = Overrun;
+
+    /// Reads the oldest byte in the buffer
+    ///
+    /// A new overrun is reported once, as soon as it's detected, and before any of the bytes still
+    /// in the buffer are returned. Those bytes precede the dropped ones and can still be read.
+    fn read(&mut self) -> nb::Result<u8, Overrun> {
+        let overruns = self.overruns();
+        if overruns != self.overruns {
+            let dropped = overruns.wrapping_sub(self.overruns);
+            self.overruns = overruns;
+            return Err(nb::Error::Other(Overrun { dropped }));
+        }
+
+        self.dequeue().ok_or(nb::Error::WouldBlock)
+    }
+}
+
+impl<'a> ::blocking::serial::read::Default<u8> for Consumer<'a> {}
+
+/// Interrupt side of a buffered receiver
+///
+/// Moves words from a serial interface into a [`RingBuffer`](struct.RingBuffer.html).
+pub struct BufferedRx<'a, S> {
+    serial: S,
+    producer: Producer<'a>,
+}
+
+impl<'a, S> BufferedRx<'a, S>
+where
+    S: Read<u8>,
+{
+    /// Creates a receiver that stores the words read from `serial` through `producer`
+    pub fn new(serial: S, producer: Producer<'a>) -> Self {
+        BufferedRx { serial, producer }
+    }
+
+    /// Reads every word that's available from the serial interface into the buffer
+    ///
+    /// Call this from the receive interrupt handler. Words that don't fit in the buffer are
+    /// dropped and counted as overruns.
+    ///
+    /// Returns the number of words read, including the dropped ones. If the serial interface
+    /// reports an error polling stops, the error is returned and the words read so far are kept.
+    pub fn poll(&mut self) -> Result<usize, S::Error> {
+        let mut count = 0;
+
+        loop {
+            match self.serial.read() {
+                Ok(word) => {
+                    if self.producer.enqueue(word).is_err() {
+                        self.producer.record_overrun();
+                    }
+                    count += 1;
+                }
+                Err(nb::Error::WouldBlock) => return Ok(count),
+                Err(nb::Error::Other(e)) => return Err(e),
+            }
+        }
+    }
+
+    /// Releases the serial interface and the producer
+    pub fn free(self) -> (S, Producer<'a>) {
+        (self.serial, self.producer)
+    }
+}
+
+/// Interrupt side of a buffered transmitter
+///
+/// Moves words from a [`RingBuffer`](struct.RingBuffer.html) to a serial interface.
+pub struct BufferedTx<'a, S> {
+    serial: S,
+    consumer: Consumer<'a>,
+    // word taken from the buffer that the serial interface hasn't accepted yet
+    pending: Option<u8>,
+}
+
+impl<'a, S> BufferedTx<'a, S>
+where
+    S: Write<u8>,
+{
+    /// Creates a transmitter that sends the words taken from `consumer` through `serial`
+    pub fn new(serial: S, consumer: Consumer<'a>) -> Self {
+        BufferedTx {
+            serial,
+            consumer,
+            pending: None,
+        }
+    }
+
+    /// Writes words from the buffer to the serial interface until either is exhausted
+    ///
+    /// Call this from the transmit interrupt handler, and once after writing to the buffer to
+    /// start the transmission.
+    ///
+    /// Returns `true` once all buffered words have been handed to the serial interface and its
+    /// `flush` has completed, at which point the transmit interrupt can be disabled and the
+    /// producer's `flush` returns `Ok`. Until then keep calling this method, e.g. from the
+    /// transmission complete interrupt. If the serial interface reports an error the word that
+    /// failed is dropped and the error returned.
+    pub fn poll(&mut self) -> Result<bool, S::Error> {
+        let rb = self.consumer.rb;
+        loop {
+            // every byte counted here is either still in the buffer or has been taken out of it
+            let written = rb.written.load(Ordering::Acquire);
+            let word = match self.pending.take().or_else(|| self.consumer.dequeue()) {
+                Some(word) => word,
+                None => match self.serial.flush() {
+                    Ok(()) => {
+                        rb.flushed.store(written, Ordering::Release);
+                        return Ok(true);
+                    }
+                    Err(nb::Error::WouldBlock) => return Ok(false),
+                    Err(nb::Error::Other(e)) => return Err(e),
+                },
+            };
+
+            match self.serial.write(word) {
+                Ok(()) => {}
+                Err(nb::Error::WouldBlock) => {
+                    self.pending = Some(word);
+                    return Ok(false);
+                }
+                Err(nb::Error::Other(e)) => return Err(e),
+            }
+        }
+    }
+
+    /// Releases the serial interface and the consumer
+    ///
+    /// A word that was taken from the buffer but not yet accepted by the serial interface is
+    /// lost.
+    pub fn free(self) -> (S, Consumer<'a>) {
+        (self.serial, self.consumer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use blocking::serial::{Read as BlockingRead, Write as BlockingWrite};
+
+    /// Serial interface that receives `incoming` and accepts at most `room` words
+    struct Serial<'a> {
+        incoming: &'a [u8],
+        sent: [u8; 8],
+        len: usize,
+        room: usize,
+        // the last words are still being shifted out
+        busy: bool,
+    }
+
+    impl<'a> Serial<'a> {
+        fn new(incoming: &'a [u8]) -> Self {
+            Serial {
+                incoming,
+                sent: [0; 8],
+                len: 0,
+                room: 0,
+                busy: false,
+            }
+        }
+    }
+
+    impl<'a> Read<u8> for Serial<'a> {
+        type Error = ();
+
+        fn read(&mut self) -> nb::Result<u8, ()> {
+            match self.incoming.split_first() {
+                Some((&first, rest)) => {
+                    self.incoming = rest;
+                    Ok(first)
+                }
+                None => Err(nb::Error::WouldBlock),
+            }
+        }
+    }
+
+    impl<'a> Write<u8> for Serial<'a> {
+        type Error = ();
+
+        fn write(&mut self, word: u8) -> nb::Result<(), ()> {
+            if self.room == 0 {
+                return Err(nb::Error::WouldBlock);
+            }
+            self.room -= 1;
+            self.sent[self.len] = word;
+            self.len += 1;
+            Ok(())
+        }
+
+        fn flush(&mut self) -> nb::Result<(), ()> {
+            if self.busy {
+                Err(nb::Error::WouldBlock)
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    #[test]
+    fn ring_buffer() {
+        let mut storage = [0; 4];
+        let mut rb = RingBuffer::new(&mut storage);
+        assert_eq!(rb.capacity(), 3);
+        let (mut p, mut c) = rb.split();
+
+        // wrap around a few times
+        for round in 0..4 {
+            assert!(p.is_empty());
+            for i in 0..3 {
+                assert_eq!(p.enqueue(round * 3 + i), Ok(()));
+            }
+            assert!(p.is_full());
+            assert_eq!(p.enqueue(0xff), Err(0xff));
+            assert_eq!(c.len(), 3);
+            for i in 0..3 {
+                assert_eq!(c.dequeue(), Some(round * 3 + i));
+            }
+            assert_eq!(c.dequeue(), None);
+        }
+    }
+
+    #[test]
+    fn rx_overrun() {
+        let mut storage = [0; 4];
+        let mut rb = RingBuffer::new(&mut storage);
+        let (p, mut c) = rb.split();
+        let mut rx = BufferedRx::new(Serial::new(b"abcde"), p);
+
+        assert_eq!(rx.poll(), Ok(5));
+        assert_eq!(c.overruns(), 2);
+
+        assert_eq!(c.read(), Err(nb::Error::Other(Overrun { dropped: 2 })));
+        let mut buffer = [0; 3];
+        c.bread_exact(&mut buffer).unwrap();
+        assert_eq!(&buffer, b"abc");
+        assert_eq!(c.read(), Err(nb::Error::WouldBlock));
+    }
+
+    #[test]
+    fn tx() {
+        let mut storage = [0; 8];
+        let mut rb = RingBuffer::new(&mut storage);
+        let (mut p, c) = rb.split();
+        let mut tx = BufferedTx::new(Serial::new(&[]), c);
+
+        p.bwrite_all(b"hello").unwrap();
+        assert_eq!(p.flush(), Err(nb::Error::WouldBlock));
+
+        tx.serial.room = 2;
+        assert_eq!(tx.poll(), Ok(false));
+        tx.serial.room = 8;
+        assert_eq!(tx.poll(), Ok(true));
+
+        assert_eq!(p.flush(), Ok(()));
+        let (serial, _) = tx.free();
+        assert_eq!(&serial.sent[..serial.len], b"hello");
+    }
+
+    #[test]
+    fn tx_flush() {
+        let mut storage = [0; 8];
+        let mut rb = RingBuffer::new(&mut storage);
+        let (mut p, c) = rb.split();
+        let mut tx = BufferedTx::new(Serial::new(&[]), c);
+        assert_eq!(p.flush(), Ok(()));
+
+        p.bwrite_all(b"hi").unwrap();
+
+        // the buffer is empty but the last word hasn't been accepted yet
+        tx.serial.room = 1;
+        assert_eq!(tx.poll(), Ok(false));
+        assert!(p.is_empty());
+        assert_eq!(p.flush(), Err(nb::Error::WouldBlock));
+
+        // every word has been accepted but not sent yet
+        tx.serial.room = 1;
+        tx.serial.busy = true;
+        assert_eq!(tx.poll(), Ok(false));
+        assert_eq!(p.flush(), Err(nb::Error::WouldBlock));
+
+        tx.serial.busy = false;
+        assert_eq!(tx.poll(), Ok(true));
+        assert_eq!(p.flush(), Ok(()));
+    }
+}
@@ -0,0 +1,575 @@
+//! Flow control
+//!
+//! Flow control lets the receiver of a serial link tell the transmitter to pause when it can't
+//! keep up, e.g. because its receive buffer is about to overflow. This module provides it on top
+//! of serial interfaces that don't support it natively:
+//!
+//! - [`RtsCts`] uses two extra lines. The transmitter only sends while its CTS (clear to send)
+//!   input is asserted, and the receiver deasserts its RTS (request to send) output, which is
+//!   wired to the peer's CTS input, when it wants the peer to pause.
+//! - [`XonXoff`] doesn't need extra lines; instead the receiver sends an [`XOFF`] byte to pause the
+//!   peer and an [`XON`] byte to resume it. These two bytes can't be used in the data.
+//!
+//! In both cases the application reports how full its receive buffer is with `receive_level`, e.g.
+//! after draining a [`buffered`] receiver, and the wrapper pauses the peer once the level reaches
+//! the high [`Watermarks`] and resumes it when the level drops to the low one.
+//!
+//! *This module is available if embedded-hal is built with the `"unproven"` feature.*
+//!
+//! [`RtsCts`]: struct.RtsCts.html
+//! [`XonXoff`]: struct.XonXoff.html
+//! [`XON`]: constant.XON.html
+//! [`XOFF`]: constant.XOFF.html
+//! [`buffered`]: ../buffered/index.html
+//! [`Watermarks`]: struct.Watermarks.html
+//!
+//! ```
+//! extern crate embedded_hal as hal;
+//!
+//! use hal::prelude::*;
+//! use hal::serial::flow::{RtsCts, Watermarks};
+//!
+//! fn main() {
+//!     let (serial, cts, rts): (Serial1, Pin, Pin) = {
+//!         // ..
+//! #       (Serial1, Pin, Pin)
+//!     };
+//!
+//!     let watermarks = Watermarks { high: 48, low: 16 };
+//!     let mut modem = RtsCts::new(serial, cts, rts, watermarks).unwrap();
+//!
+//!     // blocks while the modem deasserts CTS
+//!     modem.bwrite_all(b"AT+CSQ\r").unwrap();
+//!
+//!     // omitted: move received bytes into a 64 byte buffer
+//!     let used = 50;
+//!     // deasserts RTS
+//!     modem.receive_level(used).unwrap();
+//!     assert!(modem.is_receive_paused());
+//! }
+//!
+//! # struct Serial1;
+//! # impl hal::serial::Write<u8> for Serial1 {
+//! #     type Error = ();
+//! #     fn write(&mut self, _: u8) -> nb::Result<(), ()> { Ok(()) }
+//! #     fn flush(&mut self) -> nb::Result<(), ()> { Ok(()) }
+//! # }
+//! # struct Pin;
+//! # impl hal::digital::v2::InputPin for Pin {
+//! #     type Error = ();
+//! #     fn is_high(&self) -> Result<bool, ()> { Ok(false) }
+//! #     fn is_low(&self) -> Result<bool, ()> { Ok(true) }
+//! # }
+//! # impl hal::digital::v2::OutputPin for Pin {
+//! #     type Error = ();
+//! #     fn set_low(&mut self) -> Result<(), ()> { Ok(()) }
+//! #     fn set_high(&mut self) -> Result<(), ()> { Ok(()) }
+//! # }
+//! # extern crate nb;
+//! ```
+
+use nb;
+
+use digital::v2::{InputPin, OutputPin};
+use serial::{self, ErrorKind, Read, Write};
+
+/// Asks the peer to resume sending, DC1
+pub const XON: u8 = 0x11;
+
+/// Asks the peer to pause sending, DC3
+pub const XOFF: u8 = 0x13;
+
+/// Receive buffer levels at which the peer is paused and resumed
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Watermarks {
+    /// The peer is paused once the receive buffer holds this many words or more
+    ///
+    /// Leave room for the words the peer sends before it reacts: at least the size of its
+    /// transmit FIFO, and with `XonXoff` also the words that arrive while `XOFF` is being sent.
+    pub high: usize,
+    /// The peer is resumed once the receive buffer holds this many words or fewer
+    pub low: usize,
+}
+
+impl Watermarks {
+    /// Returns whether the peer should be paused, or `None` if it should stay as it is
+    fn update(&self, paused: bool, used: usize) -> Option<bool> {
+        if !paused && used >= self.high {
+            Some(true)
+        } else if paused && used <= self.low {
+            Some(false)
+        } else {
+            None
+        }
+    }
+}
+
+/// Flow control error
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Error<E, P> {
+    /// Serial interface error
+    Serial(E),
+    /// CTS or RTS pin error
+    Pin(P),
+}
+
+impl<E, P> serial::Error for Error<E, P>
+where
+    E: serial::Error,
+{
+    fn kind(&self) -> ErrorKind {
+        match *self {
+            Error::Serial(ref e) => e.kind(),
+            Error::Pin(_) => ErrorKind::Other,
+        }
+    }
+}
+
+/// Hardware flow control
+///
+/// Both CTS and RTS are active low, as on most UARTs: the peer may send while RTS is low.
+pub struct RtsCts<S, CTS, RTS> {
+    serial: S,
+    cts: CTS,
+    rts: RTS,
+    watermarks: Watermarks,
+    paused: bool,
+}
+
+impl<S, CTS, RTS, P> RtsCts<S, CTS, RTS>
+where
+    CTS: InputPin<Error = P>,
+    RTS: OutputPin<Error = P>,
+{
+    /// Wraps `serial`
+    ///
+    /// RTS is asserted right away.
+    pub fn new(serial: S, cts: CTS, mut rts: RTS, watermarks: Watermarks) -> Result<Self, P> {
+        rts.set_low()?;
+
+        Ok(RtsCts {
+            serial,
+            cts,
+            rts,
+            watermarks,
+            paused: false,
+        })
+    }
+
+    /// Updates RTS according to the number of words in the receive buffer
+    pub fn receive_level(&mut self, used: usize) -> Result<(), P> {
+        match self.watermarks.update(self.paused, used) {
+            Some(true) => self.rts.set_high()?,
+            Some(false) => self.rts.set_low()?,
+            None => return Ok(()),
+        }
+
+        self.paused = !self.paused;
+        Ok(())
+    }
+
+    /// Returns `true` if RTS is deasserted, i.e. the peer has been asked to pause
+    pub fn is_receive_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Releases the serial interface and the pins
+    pub fn free(self) -> (S, CTS, RTS) {
+        (self.serial, self.cts, self.rts)
+    }
+}
+
+impl<S, CTS, RTS, P> Write<u8> for RtsCts<S, CTS, RTS>
+where
+    S: Write<u8>,
+    CTS: InputPin<Error = P>,
+    RTS: OutputPin<Error = P>,
+{
+    type Error = Error<S::Error, P>;
+
+    /// Writes `word` if CTS is asserted, otherwise returns `WouldBlock`
+    fn write(&mut self, word: u8) -> nb::Result<(), Self::Error> {
+        if !self
+            .cts
+            .is_low()
+            .map_err(|e| nb::Error::Other(Error::Pin(e)))?
+        {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        match self.serial.write(word) {
+            Ok(()) => Ok(()),
+            Err(nb::Error::WouldBlock) => Err(nb::Error::WouldBlock),
+            Err(nb::Error::Other(e)) => Err(nb::Error::Other(Error::Serial(e))),
+        }
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Self::Error> {
+        match self.serial.flush() {
+            Ok(()) => Ok(()),
+            Err(nb::Error::WouldBlock) => Err(nb::Error::WouldBlock),
+            Err(nb::Error::Other(e)) => Err(nb::Error::Other(Error::Serial(e))),
+        }
+    }
+}
+
+impl<S, CTS, RTS, P> ::blocking::serial::write::Default<u8> for RtsCts<S, CTS, RTS>
+where
+    S: Write<u8>,
+    CTS: InputPin<Error = P>,
+    RTS: OutputPin<Error = P>,
+{
+}
+
+impl<S, CTS, RTS, P> Read<u8> for RtsCts<S, CTS, RTS>
+where
+    S: Read<u8>,
+    CTS: InputPin<Error = P>,
+    RTS: OutputPin<Error = P>,
+{
+    type Error = Error<S::Error, P>;
+
+    fn read(&mut self) -> nb::Result<u8, Self::Error> {
+        match self.serial.read() {
+            Ok(word) => Ok(word),
+            Err(nb::Error::WouldBlock) => Err(nb::Error::WouldBlock),
+            Err(nb::Error::Other(e)) => Err(nb::Error::Other(Error::Serial(e))),
+        }
+    }
+}
+
+/// Software flow control
+///
+/// Received `XON` and `XOFF` bytes are consumed by the wrapper and never returned by `read`.
+///
+/// `write` has to read from the serial interface to notice `XON` and `XOFF` as soon as they
+/// arrive. The data words it reads along the way are kept in a caller-provided receive buffer
+/// and returned by the next calls to `read`. Once that buffer is full, `write` stops reading and,
+/// if the peer has sent `XOFF`, returns `WouldBlock` until some words have been read. A few bytes
+/// of buffer are enough if the application keeps reading while it writes.
+pub struct XonXoff<'a, S>
+where
+    S: Read<u8>,
+{
+    serial: S,
+    watermarks: Watermarks,
+    // the peer sent `XOFF`
+    paused: bool,
+    // we sent `XOFF`
+    receive_paused: bool,
+    // data words received by `write`, `len` of them starting at `start`
+    buffer: &'a mut [u8],
+    start: usize,
+    len: usize,
+    // receive error seen by `write`, reported by the next `read`
+    error: Option<S::Error>,
+}
+
+impl<'a, S, E> XonXoff<'a, S>
+where
+    S: Read<u8, Error = E> + Write<u8, Error = E>,
+{
+    /// Wraps `serial`, keeping the words received while writing in `buffer`
+    ///
+    /// # Panics
+    ///
+    /// If `buffer` is empty
+    pub fn new(serial: S, buffer: &'a mut [u8], watermarks: Watermarks) -> Self {
+        assert!(!buffer.is_empty(), "receive buffer can't be empty");
+
+        XonXoff {
+            serial,
+            watermarks,
+            paused: false,
+            receive_paused: false,
+            buffer,
+            start: 0,
+            len: 0,
+            error: None,
+        }
+    }
+
+    /// Sends `XOFF` or `XON` according to the number of words in the receive buffer
+    ///
+    /// Returns `WouldBlock` if the serial interface isn't ready to send; call this again until it
+    /// returns `Ok`.
+    pub fn receive_level(&mut self, used: usize) -> nb::Result<(), E> {
+        let word = match self.watermarks.update(self.receive_paused, used) {
+            Some(true) => XOFF,
+            Some(false) => XON,
+            None => return Ok(()),
+        };
+
+        self.serial.write(word)?;
+        self.receive_paused = !self.receive_paused;
+        Ok(())
+    }
+
+    /// Returns `true` if `XOFF` has been sent, i.e. the peer has been asked to pause
+    pub fn is_receive_paused(&self) -> bool {
+        self.receive_paused
+    }
+
+    /// Returns `true` if the peer has sent `XOFF`
+    pub fn is_transmit_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Releases the serial interface
+    ///
+    /// Words that were received while writing but not read yet are lost.
+    pub fn free(self) -> S {
+        self.serial
+    }
+
+    /// Reads until a word other than `XON` or `XOFF` is received
+    fn receive(&mut self) -> nb::Result<u8, E> {
+        loop {
+            match self.serial.read()? {
+                XON => self.paused = false,
+                XOFF => self.paused = true,
+                word => return Ok(word),
+            }
+        }
+    }
+
+    /// Reads every available word into the receive buffer, as long as there's room
+    fn receive_all(&mut self) {
+        while self.len < self.buffer.len() {
+            match self.receive() {
+                Ok(word) => {
+                    let end = (self.start + self.len) % self.buffer.len();
+                    self.buffer[end] = word;
+                    self.len += 1;
+                }
+                Err(nb::Error::WouldBlock) => break,
+                Err(nb::Error::Other(e)) => {
+                    // only the first error is kept
+                    if self.error.is_none() {
+                        self.error = Some(e);
+                    }
+                    break;
+                }
+            }
+        }
+    }
+}
+
+impl<'a, S, E> Write<u8> for XonXoff<'a, S>
+where
+    S: Read<u8, Error = E> + Write<u8, Error = E>,
+{
+    type Error = E;
+
+    /// Writes `word` unless the peer has sent `XOFF`, in which case `WouldBlock` is returned
+    fn write(&mut self, word: u8) -> nb::Result<(), E> {
+        // check for `XON` / `XOFF` even while not paused, to stop as soon as possible
+        self.receive_all();
+
+        if self.paused {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        self.serial.write(word)
+    }
+
+    fn flush(&mut self) -> nb::Result<(), E> {
+        self.serial.flush()
+    }
+}
+
+impl<'a, S, E> ::blocking::serial::write::Default<u8> for XonXoff<'a, S> where
+    S: Read<u8, Error = E> + Write<u8, Error = E>
+{
+}
+
+impl<'a, S, E> Read<u8> for XonXoff<'a, S>
+where
+    S: Read<u8, Error = E> + Write<u8, Error = E>,
+{
+    type Error = E;
+
+    /// Returns the words received while writing first, then an error seen while writing, if any
+    fn read(&mut self) -> nb::Result<u8, E> {
+        if self.len > 0 {
+            let word = self.buffer[self.start];
+            self.start = (self.start + 1) % self.buffer.len();
+            self.len -= 1;
+            return Ok(word);
+        }
+
+        match self.error.take() {
+            Some(e) => Err(nb::Error::Other(e)),
+            None => self.receive(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::cell::Cell;
+
+    use blocking::serial::Write as BlockingWrite;
+
+    /// Serial interface that receives `incoming` and records the words written
+    struct Serial<'a> {
+        incoming: &'a [u8],
+        sent: [u8; 8],
+        len: usize,
+    }
+
+    impl<'a> Serial<'a> {
+        fn new(incoming: &'a [u8]) -> Self {
+            Serial {
+                incoming,
+                sent: [0; 8],
+                len: 0,
+            }
+        }
+    }
+
+    impl<'a> Read<u8> for Serial<'a> {
+        type Error = ();
+
+        fn read(&mut self) -> nb::Result<u8, ()> {
+            match self.incoming.split_first() {
+                Some((&first, rest)) => {
+                    self.incoming = rest;
+                    Ok(first)
+                }
+                None => Err(nb::Error::WouldBlock),
+            }
+        }
+    }
+
+    impl<'a> Write<u8> for Serial<'a> {
+        type Error = ();
+
+        fn write(&mut self, word: u8) -> nb::Result<(), ()> {
+            self.sent[self.len] = word;
+            self.len += 1;
+            Ok(())
+        }
+
+        fn flush(&mut self) -> nb::Result<(), ()> {
+            Ok(())
+        }
+    }
+
+    struct Pin<'a>(&'a Cell<bool>);
+
+    impl<'a> InputPin for Pin<'a> {
+        type Error = ();
+
+        fn is_high(&self) -> Result<bool, ()> {
+            Ok(self.0.get())
+        }
+
+        fn is_low(&self) -> Result<bool, ()> {
+            Ok(!self.0.get())
+        }
+    }
+
+    impl<'a> OutputPin for Pin<'a> {
+        type Error = ();
+
+        fn set_low(&mut self) -> Result<(), ()> {
+            self.0.set(false);
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), ()> {
+            self.0.set(true);
+            Ok(())
+        }
+    }
+
+    const WATERMARKS: Watermarks = Watermarks { high: 6, low: 2 };
+
+    #[test]
+    fn cts_gates_writes() {
+        let cts = Cell::new(true);
+        let rts = Cell::new(true);
+        let mut serial = RtsCts::new(Serial::new(&[]), Pin(&cts), Pin(&rts), WATERMARKS).unwrap();
+
+        assert_eq!(serial.write(1), Err(nb::Error::WouldBlock));
+        cts.set(false);
+        assert_eq!(serial.write(2), Ok(()));
+
+        let (serial, _, _) = serial.free();
+        assert_eq!(&serial.sent[..serial.len], &[2]);
+    }
+
+    #[test]
+    fn rts_watermarks() {
+        let cts = Cell::new(false);
+        let rts = Cell::new(true);
+        let mut serial = RtsCts::new(Serial::new(&[]), Pin(&cts), Pin(&rts), WATERMARKS).unwrap();
+        assert!(!rts.get());
+
+        for &(used, paused) in &[(5, false), (6, true), (3, true), (2, false), (5, false)] {
+            serial.receive_level(used).unwrap();
+            assert_eq!(serial.is_receive_paused(), paused);
+            assert_eq!(rts.get(), paused);
+        }
+    }
+
+    #[test]
+    fn xon_xoff_filters_and_pauses() {
+        let mut buffer = [0; 2];
+        let mut serial = XonXoff::new(
+            Serial::new(&[1, XOFF, 2, 3, XON, 4]),
+            &mut buffer,
+            WATERMARKS,
+        );
+
+        assert_eq!(serial.read(), Ok(1));
+        // reads `XOFF` and keeps 2 and 3; the buffer is then full
+        assert_eq!(serial.write(10), Err(nb::Error::WouldBlock));
+        assert!(serial.is_transmit_paused());
+        assert_eq!(serial.write(10), Err(nb::Error::WouldBlock));
+        assert_eq!(serial.read(), Ok(2));
+        // reads `XON`, keeps 4
+        assert_eq!(serial.write(10), Ok(()));
+        assert_eq!(serial.read(), Ok(3));
+        assert_eq!(serial.read(), Ok(4));
+        assert_eq!(serial.read(), Err(nb::Error::WouldBlock));
+
+        let serial = serial.free();
+        assert_eq!(&serial.sent[..serial.len], &[10]);
+    }
+
+    #[test]
+    fn xon_xoff_data_while_paused() {
+        let mut buffer = [0; 4];
+
+        // the data byte doesn't hide the `XON` that follows it
+        let mut serial = XonXoff::new(Serial::new(&[XOFF, 7, XON]), &mut buffer, WATERMARKS);
+        serial.bwrite_all(b"ab").unwrap();
+        assert_eq!(serial.read(), Ok(7));
+        let sent = serial.free();
+        assert_eq!(&sent.sent[..sent.len], b"ab");
+
+        // nor the `XOFF` that follows it
+        let mut serial = XonXoff::new(Serial::new(&[7, XOFF]), &mut buffer, WATERMARKS);
+        assert_eq!(serial.write(b'a'), Err(nb::Error::WouldBlock));
+        assert_eq!(serial.read(), Ok(7));
+        assert_eq!(serial.free().len, 0);
+    }
+
+    #[test]
+    fn xon_xoff_watermarks() {
+        let mut buffer = [0; 1];
+        let mut serial = XonXoff::new(Serial::new(&[]), &mut buffer, WATERMARKS);
+
+        for &used in &[5, 6, 7, 3, 2, 1, 6] {
+            serial.receive_level(used).unwrap();
+        }
+        assert!(serial.is_receive_paused());
+
+        let serial = serial.free();
+        assert_eq!(&serial.sent[..serial.len], &[XOFF, XON, XOFF]);
+    }
+}
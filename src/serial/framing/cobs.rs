@@ -0,0 +1,309 @@
+//! Consistent Overhead Byte Stuffing
+//!
+//! COBS removes every zero byte from the data so that a single zero byte can delimit frames. The
+//! data is split into blocks of at most 254 non zero bytes, each one prefixed with a code byte that
+//! tells where the next zero byte would have been.
+//!
+//! Every frame written by this module is terminated with a zero byte. The decoder ignores empty
+//! frames, i.e. consecutive zero bytes, so a zero byte can also be sent before a frame to flush out
+//! any garbage the receiver may have picked up.
+
+use core::cmp;
+
+use blocking::serial::Write;
+
+use super::{Buffer, DecodeError, FrameDecoder};
+
+/// Frame delimiter
+pub const DELIMITER: u8 = 0x00;
+
+/// Returns the maximum size of a frame that contains `len` bytes of data, including the delimiter
+pub const fn max_encoded_len(len: usize) -> usize {
+    len + len / 254 + 2
+}
+
+fn encode_with<E, F>(data: &[u8], mut emit: F) -> Result<(), E>
+where
+    F: FnMut(&[u8]) -> Result<(), E>,
+{
+    let mut rest = data;
+    loop {
+        let max = cmp::min(rest.len(), 254);
+        let n = rest[..max].iter().position(|&b| b == 0).unwrap_or(max);
+
+        emit(&[n as u8 + 1])?;
+        emit(&rest[..n])?;
+
+        if n < max {
+            // skip the zero byte, which is implied by the code byte
+            rest = &rest[n + 1..];
+        } else if n < rest.len() {
+            // a full block; the code byte doesn't imply a zero byte
+            rest = &rest[n..];
+        } else {
+            break;
+        }
+    }
+
+    emit(&[DELIMITER])
+}
+
+/// Encodes `data` into `buffer`, which must be at least [`max_encoded_len`] bytes long to hold any
+/// frame of that size
+///
+/// Returns the size of the frame, including the delimiter, or `None` if it doesn't fit in
+/// `buffer`.
+///
+/// [`max_encoded_len`]: fn.max_encoded_len.html
+pub fn encode(data: &[u8], buffer: &mut [u8]) -> Option<usize> {
+    let mut len = 0;
+    encode_with::<(), _>(data, |bytes| {
+        let end = len + bytes.len();
+        buffer.get_mut(len..end).ok_or(())?.copy_from_slice(bytes);
+        len = end;
+        Ok(())
+    })
+    .ok()?;
+
+    Some(len)
+}
+
+/// Encodes `data` and writes the frame to `serial`
+pub fn write_frame<S>(serial: &mut S, data: &[u8]) -> Result<(), S::Error>
+where
+    S: Write<u8>,
+{
+    encode_with(data, |bytes| serial.bwrite_all(bytes))
+}
+
+/// COBS decoder
+pub struct Decoder<'a> {
+    buffer: Buffer<'a>,
+    // code byte of the current block; zero before the first block of a frame
+    code: u8,
+    // number of data bytes left in the current block
+    remaining: u8,
+}
+
+impl<'a> Decoder<'a> {
+    /// Creates a decoder that stores frames in `buffer`
+    ///
+    /// Frames longer than `buffer` are discarded with a `DecodeError::Overflow` error.
+    pub fn new(buffer: &'a mut [u8]) -> Self {
+        Decoder {
+            buffer: Buffer::new(buffer),
+            code: 0,
+            remaining: 0,
+        }
+    }
+}
+
+impl<'a> FrameDecoder for Decoder<'a> {
+    fn feed(&mut self, byte: u8) -> Result<Option<usize>, DecodeError> {
+        self.buffer.frame = 0;
+
+        if byte == DELIMITER {
+            let result = if self.buffer.discarding || self.code == 0 {
+                Ok(None)
+            } else if self.remaining != 0 {
+                Err(DecodeError::Malformed)
+            } else {
+                Ok(self.buffer.complete())
+            };
+
+            self.buffer.end();
+            self.code = 0;
+            self.remaining = 0;
+
+            return result;
+        }
+
+        if self.buffer.discarding {
+            return Ok(None);
+        }
+
+        if self.remaining == 0 {
+            if self.code != 0 && self.code != 0xff {
+                self.buffer.push(0)?;
+            }
+            self.code = byte;
+            self.remaining = byte - 1;
+        } else {
+            self.buffer.push(byte)?;
+            self.remaining -= 1;
+        }
+
+        Ok(None)
+    }
+
+    fn frame(&self) -> &[u8] {
+        self.buffer.frame()
+    }
+
+    fn reset(&mut self) {
+        self.buffer.discard();
+        self.code = 0;
+        self.remaining = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(data: &[u8], encoded: &[u8]) {
+        let mut buffer = [0; 600];
+        let len = encode(data, &mut buffer).unwrap();
+        assert_eq!(&buffer[..len], encoded);
+        assert!(len <= max_encoded_len(data.len()));
+
+        let mut buffer = [0; 600];
+        let mut decoder = Decoder::new(&mut buffer);
+        let (last, rest) = encoded.split_last().unwrap();
+        for &byte in rest {
+            assert_eq!(decoder.feed(byte), Ok(None));
+        }
+        assert_eq!(decoder.feed(*last), Ok(Some(data.len())));
+        assert_eq!(decoder.frame(), data);
+    }
+
+    #[test]
+    fn examples() {
+        check(&[], &[0x01, 0x00]);
+        check(&[0x00], &[0x01, 0x01, 0x00]);
+        check(&[0x00, 0x00], &[0x01, 0x01, 0x01, 0x00]);
+        check(&[0x00, 0x11, 0x00], &[0x01, 0x02, 0x11, 0x01, 0x00]);
+        check(
+            &[0x11, 0x22, 0x00, 0x33],
+            &[0x03, 0x11, 0x22, 0x02, 0x33, 0x00],
+        );
+        check(
+            &[0x11, 0x22, 0x33, 0x44],
+            &[0x05, 0x11, 0x22, 0x33, 0x44, 0x00],
+        );
+        check(
+            &[0x11, 0x00, 0x00, 0x00],
+            &[0x02, 0x11, 0x01, 0x01, 0x01, 0x00],
+        );
+    }
+
+    #[test]
+    fn long_blocks() {
+        let mut data = [0; 256];
+        let mut encoded = [0; 260];
+
+        // 0x01 ..= 0xfe
+        for i in 0..254 {
+            data[i] = i as u8 + 1;
+            encoded[i + 1] = i as u8 + 1;
+        }
+        encoded[0] = 0xff;
+        check(&data[..254], &encoded[..256]);
+
+        // 0x01 ..= 0xff
+        data[254] = 0xff;
+        encoded[255] = 0x02;
+        encoded[256] = 0xff;
+        check(&data[..255], &encoded[..258]);
+
+        // 0x01 ..= 0xfe, 0x00
+        data[254] = 0x00;
+        encoded[255] = 0x01;
+        encoded[256] = 0x01;
+        check(&data[..255], &encoded[..258]);
+    }
+
+    #[test]
+    fn buffer_too_small() {
+        let mut buffer = [0; 5];
+        assert_eq!(encode(&[1, 2, 3, 4], &mut buffer), None);
+        assert_eq!(encode(&[1, 2, 3], &mut buffer), Some(5));
+    }
+
+    #[test]
+    fn overflow() {
+        let mut buffer = [0; 2];
+        let mut decoder = Decoder::new(&mut buffer);
+
+        for &byte in &[0x04, 0x11, 0x22] {
+            assert_eq!(decoder.feed(byte), Ok(None));
+        }
+        assert_eq!(decoder.feed(0x33), Err(DecodeError::Overflow));
+        assert_eq!(decoder.feed(0x00), Ok(None));
+
+        for &byte in &[0x02, 0x44] {
+            assert_eq!(decoder.feed(byte), Ok(None));
+        }
+        assert_eq!(decoder.feed(0x00), Ok(Some(1)));
+        assert_eq!(decoder.frame(), &[0x44]);
+    }
+
+    #[test]
+    fn recovers_from_corrupt_frames() {
+        let mut buffer = [0; 8];
+        let mut decoder = Decoder::new(&mut buffer);
+
+        // leading delimiters are ignored
+        assert_eq!(decoder.feed(0x00), Ok(None));
+        assert_eq!(decoder.feed(0x00), Ok(None));
+
+        // truncated frame
+        for &byte in &[0x05, 0x11, 0x22] {
+            assert_eq!(decoder.feed(byte), Ok(None));
+        }
+        assert_eq!(decoder.feed(0x00), Err(DecodeError::Malformed));
+
+        for &byte in &[0x02, 0x33, 0x01] {
+            assert_eq!(decoder.feed(byte), Ok(None));
+        }
+        assert_eq!(decoder.feed(0x00), Ok(Some(2)));
+        assert_eq!(decoder.frame(), &[0x33, 0x00]);
+
+        // the frame is only available until the next byte
+        assert_eq!(decoder.feed(0x02), Ok(None));
+        assert_eq!(decoder.frame(), &[]);
+
+        // a reset discards the rest of the frame
+        decoder.reset();
+        assert_eq!(decoder.feed(0x44), Ok(None));
+        assert_eq!(decoder.feed(0x00), Ok(None));
+        for &byte in &[0x02, 0x55] {
+            assert_eq!(decoder.feed(byte), Ok(None));
+        }
+        assert_eq!(decoder.feed(0x00), Ok(Some(1)));
+        assert_eq!(decoder.frame(), &[0x55]);
+    }
+
+    #[test]
+    fn write_frame_matches_encode() {
+        struct Serial {
+            sent: [u8; 16],
+            len: usize,
+        }
+
+        impl Write<u8> for Serial {
+            type Error = ();
+
+            fn bwrite_all(&mut self, buffer: &[u8]) -> Result<(), ()> {
+                self.sent[self.len..self.len + buffer.len()].copy_from_slice(buffer);
+                self.len += buffer.len();
+                Ok(())
+            }
+
+            fn bflush(&mut self) -> Result<(), ()> {
+                Ok(())
+            }
+        }
+
+        let data = [0x00, 0x11, 0x22, 0x00, 0x00, 0x33];
+        let mut serial = Serial {
+            sent: [0; 16],
+            len: 0,
+        };
+        write_frame(&mut serial, &data).unwrap();
+
+        let mut buffer = [0; 16];
+        let len = encode(&data, &mut buffer).unwrap();
+        assert_eq!(&serial.sent[..serial.len], &buffer[..len]);
+    }
+}
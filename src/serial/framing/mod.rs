@@ -0,0 +1,266 @@
+//! Packet framing over serial interfaces
+//!
+//! A serial interface transfers a stream of bytes; to send packets over it the receiver has to be
+//! able to find out where each packet starts and ends, and to resynchronize after bytes have been
+//! lost or corrupted. This module provides two common framing schemes:
+//!
+//! - [`cobs`], Consistent Overhead Byte Stuffing, which delimits frames with a zero byte and has a
+//!   worst case overhead of one byte per 254 bytes of data.
+//! - [`slip`], the Serial Line Internet Protocol framing of RFC 1055, which is simpler but can
+//!   double the size of a frame in the worst case.
+//!
+//! Neither allocates: encoders write straight to the serial interface or to a caller provided
+//! buffer and decoders reassemble frames in a caller provided buffer, one byte at a time.
+//!
+//! *This module is available if embedded-hal is built with the `"unproven"` feature.*
+//!
+//! [`cobs`]: cobs/index.html
+//! [`slip`]: slip/index.html
+//!
+//! ```
+//! extern crate embedded_hal as hal;
+//! #[macro_use(block)]
+//! extern crate nb;
+//!
+//! use hal::prelude::*;
+//! use hal::serial::framing::cobs;
+//!
+//! fn main() {
+//!     let mut serial: Serial1 = {
+//!         // ..
+//! #       Serial1 { buffer: [0; 16], len: 0, read: 0 }
+//!     };
+//!
+//!     cobs::write_frame(&mut serial, &[0x11, 0x00, 0x22]).unwrap();
+//!
+//!     let mut buffer = [0; 64];
+//!     let mut decoder = cobs::Decoder::new(&mut buffer);
+//!     let frame = block!(decoder.read_frame(&mut serial)).unwrap();
+//!     assert_eq!(frame, &[0x11, 0x00, 0x22]);
+//! }
+//!
+//! # // loopback
+//! # struct Serial1 { buffer: [u8; 16], len: usize, read: usize }
+//! # impl hal::serial::Write<u8> for Serial1 {
+//! #     type Error = ();
+//! #     fn write(&mut self, word: u8) -> nb::Result<(), ()> {
+//! #         self.buffer[self.len] = word;
+//! #         self.len += 1;
+//! #         Ok(())
+//! #     }
+//! #     fn flush(&mut self) -> nb::Result<(), ()> { Ok(()) }
+//! # }
+//! # impl hal::blocking::serial::write::Default<u8> for Serial1 {}
+//! # impl hal::serial::Read<u8> for Serial1 {
+//! #     type Error = ();
+//! #     fn read(&mut self) -> nb::Result<u8, ()> {
+//! #         if self.read == self.len { return Err(nb::Error::WouldBlock) }
+//! #         self.read += 1;
+//! #         Ok(self.buffer[self.read - 1])
+//! #     }
+//! # }
+//! ```
+
+use nb;
+
+use serial::{self, ErrorKind};
+
+pub mod cobs;
+pub mod slip;
+
+/// Frame decoding error
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DecodeError {
+    /// The frame doesn't fit in the decoder's buffer
+    Overflow,
+    /// The frame is not encoded correctly, e.g. because bytes were lost or corrupted
+    Malformed,
+}
+
+/// Error reading a frame from a serial interface
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Error<E> {
+    /// Serial interface error
+    Serial(E),
+    /// The received frame couldn't be decoded
+    Decode(DecodeError),
+}
+
+impl<E> serial::Error for Error<E>
+where
+    E: serial::Error,
+{
+    fn kind(&self) -> ErrorKind {
+        match *self {
+            Error::Serial(ref e) => e.kind(),
+            Error::Decode(_) => ErrorKind::Other,
+        }
+    }
+}
+
+/// Incremental frame decoder
+///
+/// After an error the rest of the frame is discarded; decoding resumes with the next frame.
+pub trait FrameDecoder {
+    /// Processes the next received byte
+    ///
+    /// Returns the length of the frame once the byte that ends it has been processed. The frame
+    /// can then be retrieved with [`frame`](#tymethod.frame).
+    fn feed(&mut self, byte: u8) -> Result<Option<usize>, DecodeError>;
+
+    /// Returns the last complete frame
+    ///
+    /// The frame is only available until the next call to `feed`, after which this returns an
+    /// empty slice.
+    fn frame(&self) -> &[u8];
+
+    /// Discards the partially received frame
+    ///
+    /// Bytes are then ignored until the start of the next frame.
+    fn reset(&mut self);
+
+    /// Reads bytes from `serial` until a frame is complete
+    ///
+    /// Returns `WouldBlock` once `serial` runs out of bytes; the partially received frame is kept
+    /// so this can be called again when more bytes are available.
+    fn read_frame<S>(&mut self, serial: &mut S) -> nb::Result<&[u8], Error<S::Error>>
+    where
+        S: serial::Read<u8>,
+    {
+        loop {
+            let byte = match serial.read() {
+                Ok(byte) => byte,
+                Err(nb::Error::WouldBlock) => return Err(nb::Error::WouldBlock),
+                Err(nb::Error::Other(e)) => return Err(nb::Error::Other(Error::Serial(e))),
+            };
+
+            match self.feed(byte) {
+                Ok(Some(_)) => return Ok(self.frame()),
+                Ok(None) => {}
+                Err(e) => return Err(nb::Error::Other(Error::Decode(e))),
+            }
+        }
+    }
+}
+
+/// Stores the decoded bytes of a frame
+struct Buffer<'a> {
+    buffer: &'a mut [u8],
+    len: usize,
+    // length of the last complete frame, which is stored at the start of `buffer`
+    frame: usize,
+    // an error occurred; ignore everything until the end of the frame
+    discarding: bool,
+}
+
+impl<'a> Buffer<'a> {
+    fn new(buffer: &'a mut [u8]) -> Self {
+        Buffer {
+            buffer,
+            len: 0,
+            frame: 0,
+            discarding: false,
+        }
+    }
+
+    fn push(&mut self, byte: u8) -> Result<(), DecodeError> {
+        if self.len == self.buffer.len() {
+            return Err(self.fail(DecodeError::Overflow));
+        }
+
+        self.buffer[self.len] = byte;
+        self.len += 1;
+        Ok(())
+    }
+
+    fn fail(&mut self, error: DecodeError) -> DecodeError {
+        self.discard();
+        error
+    }
+
+    fn discard(&mut self) {
+        self.len = 0;
+        self.discarding = true;
+    }
+
+    fn end(&mut self) {
+        self.len = 0;
+        self.discarding = false;
+    }
+
+    fn complete(&mut self) -> Option<usize> {
+        self.frame = self.len;
+        self.len = 0;
+        Some(self.frame)
+    }
+
+    fn frame(&self) -> &[u8] {
+        &self.buffer[..self.frame]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Serial interface that receives `incoming`, then fails
+    struct Serial<'a> {
+        incoming: &'a [u8],
+        blocked: bool,
+    }
+
+    impl<'a> serial::Read<u8> for Serial<'a> {
+        type Error = ErrorKind;
+
+        fn read(&mut self) -> nb::Result<u8, ErrorKind> {
+            if self.blocked {
+                return Err(nb::Error::WouldBlock);
+            }
+
+            match self.incoming.split_first() {
+                Some((&first, rest)) => {
+                    self.incoming = rest;
+                    Ok(first)
+                }
+                None => Err(nb::Error::Other(ErrorKind::Framing)),
+            }
+        }
+    }
+
+    #[test]
+    fn read_frame() {
+        let mut buffer = [0; 8];
+        let mut decoder = slip::Decoder::new(&mut buffer);
+        let mut serial = Serial {
+            incoming: &[0xc0, 1, 2, 0xc0, 3, 0xdb, 0xdc, 0xc0, 0xdb],
+            blocked: false,
+        };
+
+        assert_eq!(decoder.read_frame(&mut serial), Ok(&[1, 2][..]));
+        assert_eq!(decoder.read_frame(&mut serial), Ok(&[3, 0xc0][..]));
+        assert_eq!(
+            decoder.read_frame(&mut serial),
+            Err(nb::Error::Other(Error::Serial(ErrorKind::Framing)))
+        );
+
+        serial.blocked = true;
+        assert_eq!(decoder.read_frame(&mut serial), Err(nb::Error::WouldBlock));
+    }
+
+    #[test]
+    fn read_frame_decode_error() {
+        let mut buffer = [0; 8];
+        let mut decoder = cobs::Decoder::new(&mut buffer);
+        let mut serial = Serial {
+            // the first frame lost a byte
+            incoming: &[0x03, 0x11, 0x00, 0x02, 0x22, 0x00],
+            blocked: false,
+        };
+
+        assert_eq!(
+            decoder.read_frame(&mut serial),
+            Err(nb::Error::Other(Error::Decode(DecodeError::Malformed)))
+        );
+        assert_eq!(decoder.read_frame(&mut serial), Ok(&[0x22][..]));
+    }
+}
@@ -0,0 +1,242 @@
+//! Serial Line Internet Protocol framing, as specified in RFC 1055
+//!
+//! Frames are delimited with an `END` byte. `END` and `ESC` bytes in the data are replaced with
+//! two byte escape sequences that start with `ESC`.
+//!
+//! Every frame written by this module starts and ends with an `END` byte; the leading one flushes
+//! out any garbage the receiver may have picked up. The decoder ignores empty frames, which means
+//! that frames without data can't be sent.
+
+use blocking::serial::Write;
+
+use super::{Buffer, DecodeError, FrameDecoder};
+
+/// Frame delimiter
+pub const END: u8 = 0xc0;
+
+/// Starts an escape sequence
+pub const ESC: u8 = 0xdb;
+
+/// Escaped `END` byte, when following `ESC`
+pub const ESC_END: u8 = 0xdc;
+
+/// Escaped `ESC` byte, when following `ESC`
+pub const ESC_ESC: u8 = 0xdd;
+
+/// Returns the maximum size of a frame that contains `len` bytes of data, including the delimiters
+pub const fn max_encoded_len(len: usize) -> usize {
+    2 * len + 2
+}
+
+fn encode_with<E, F>(data: &[u8], mut emit: F) -> Result<(), E>
+where
+    F: FnMut(&[u8]) -> Result<(), E>,
+{
+    emit(&[END])?;
+
+    let mut rest = data;
+    while let Some(n) = rest.iter().position(|&b| b == END || b == ESC) {
+        emit(&rest[..n])?;
+        emit(if rest[n] == END {
+            &[ESC, ESC_END]
+        } else {
+            &[ESC, ESC_ESC]
+        })?;
+        rest = &rest[n + 1..];
+    }
+    emit(rest)?;
+
+    emit(&[END])
+}
+
+/// Encodes `data` into `buffer`, which must be at least [`max_encoded_len`] bytes long to hold any
+/// frame of that size
+///
+/// Returns the size of the frame, including the delimiters, or `None` if it doesn't fit in
+/// `buffer`.
+///
+/// [`max_encoded_len`]: fn.max_encoded_len.html
+pub fn encode(data: &[u8], buffer: &mut [u8]) -> Option<usize> {
+    let mut len = 0;
+    encode_with::<(), _>(data, |bytes| {
+        let end = len + bytes.len();
+        buffer.get_mut(len..end).ok_or(())?.copy_from_slice(bytes);
+        len = end;
+        Ok(())
+    })
+    .ok()?;
+
+    Some(len)
+}
+
+/// Encodes `data` and writes the frame to `serial`
+pub fn write_frame<S>(serial: &mut S, data: &[u8]) -> Result<(), S::Error>
+where
+    S: Write<u8>,
+{
+    encode_with(data, |bytes| serial.bwrite_all(bytes))
+}
+
+/// SLIP decoder
+pub struct Decoder<'a> {
+    buffer: Buffer<'a>,
+    // the previous byte was `ESC`
+    escaped: bool,
+}
+
+impl<'a> Decoder<'a> {
+    /// Creates a decoder that stores frames in `buffer`
+    ///
+    /// Frames longer than `buffer` are discarded with a `DecodeError::Overflow` error.
+    pub fn new(buffer: &'a mut [u8]) -> Self {
+        Decoder {
+            buffer: Buffer::new(buffer),
+            escaped: false,
+        }
+    }
+}
+
+impl<'a> FrameDecoder for Decoder<'a> {
+    fn feed(&mut self, byte: u8) -> Result<Option<usize>, DecodeError> {
+        self.buffer.frame = 0;
+
+        if byte == END {
+            let result = if self.buffer.discarding || (self.buffer.len == 0 && !self.escaped) {
+                Ok(None)
+            } else if self.escaped {
+                Err(DecodeError::Malformed)
+            } else {
+                Ok(self.buffer.complete())
+            };
+
+            self.buffer.end();
+            self.escaped = false;
+
+            return result;
+        }
+
+        if self.buffer.discarding {
+            return Ok(None);
+        }
+
+        if self.escaped {
+            self.escaped = false;
+            match byte {
+                ESC_END => self.buffer.push(END)?,
+                ESC_ESC => self.buffer.push(ESC)?,
+                _ => return Err(self.buffer.fail(DecodeError::Malformed)),
+            }
+        } else if byte == ESC {
+            self.escaped = true;
+        } else {
+            self.buffer.push(byte)?;
+        }
+
+        Ok(None)
+    }
+
+    fn frame(&self) -> &[u8] {
+        self.buffer.frame()
+    }
+
+    fn reset(&mut self) {
+        self.buffer.discard();
+        self.escaped = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(data: &[u8], encoded: &[u8]) {
+        let mut buffer = [0; 32];
+        let len = encode(data, &mut buffer).unwrap();
+        assert_eq!(&buffer[..len], encoded);
+        assert!(len <= max_encoded_len(data.len()));
+
+        let mut buffer = [0; 32];
+        let mut decoder = Decoder::new(&mut buffer);
+        let (last, rest) = encoded.split_last().unwrap();
+        for &byte in rest {
+            assert_eq!(decoder.feed(byte), Ok(None));
+        }
+        assert_eq!(decoder.feed(*last), Ok(Some(data.len())));
+        assert_eq!(decoder.frame(), data);
+    }
+
+    #[test]
+    fn examples() {
+        check(&[0x01, 0x02], &[END, 0x01, 0x02, END]);
+        check(&[END], &[END, ESC, ESC_END, END]);
+        check(&[ESC], &[END, ESC, ESC_ESC, END]);
+        check(
+            &[0x01, END, ESC, 0x02, ESC_END, ESC_ESC],
+            &[
+                END, 0x01, ESC, ESC_END, ESC, ESC_ESC, 0x02, ESC_END, ESC_ESC, END,
+            ],
+        );
+        check(&[END, END], &[END, ESC, ESC_END, ESC, ESC_END, END]);
+    }
+
+    #[test]
+    fn buffer_too_small() {
+        let mut buffer = [0; 4];
+        assert_eq!(encode(&[END], &mut buffer), Some(4));
+        assert_eq!(encode(&[END, 0x01], &mut buffer), None);
+    }
+
+    #[test]
+    fn overflow() {
+        let mut buffer = [0; 2];
+        let mut decoder = Decoder::new(&mut buffer);
+
+        for &byte in &[END, 0x01, 0x02] {
+            assert_eq!(decoder.feed(byte), Ok(None));
+        }
+        assert_eq!(decoder.feed(0x03), Err(DecodeError::Overflow));
+        assert_eq!(decoder.feed(0x04), Ok(None));
+        assert_eq!(decoder.feed(END), Ok(None));
+
+        for &byte in &[ESC, ESC_ESC, 0x05] {
+            assert_eq!(decoder.feed(byte), Ok(None));
+        }
+        assert_eq!(decoder.feed(END), Ok(Some(2)));
+        assert_eq!(decoder.frame(), &[ESC, 0x05]);
+    }
+
+    #[test]
+    fn recovers_from_corrupt_frames() {
+        let mut buffer = [0; 8];
+        let mut decoder = Decoder::new(&mut buffer);
+
+        // invalid escape sequence
+        for &byte in &[END, 0x01, ESC] {
+            assert_eq!(decoder.feed(byte), Ok(None));
+        }
+        assert_eq!(decoder.feed(0x02), Err(DecodeError::Malformed));
+        assert_eq!(decoder.feed(0x03), Ok(None));
+        assert_eq!(decoder.feed(END), Ok(None));
+
+        // frame that ends in the middle of an escape sequence
+        for &byte in &[0x04, ESC] {
+            assert_eq!(decoder.feed(byte), Ok(None));
+        }
+        assert_eq!(decoder.feed(END), Err(DecodeError::Malformed));
+
+        for &byte in &[0x05, 0x06] {
+            assert_eq!(decoder.feed(byte), Ok(None));
+        }
+        assert_eq!(decoder.feed(END), Ok(Some(2)));
+        assert_eq!(decoder.frame(), &[0x05, 0x06]);
+
+        // a reset discards the rest of the frame
+        assert_eq!(decoder.feed(0x07), Ok(None));
+        decoder.reset();
+        assert_eq!(decoder.feed(0x08), Ok(None));
+        assert_eq!(decoder.feed(END), Ok(None));
+        assert_eq!(decoder.feed(0x09), Ok(None));
+        assert_eq!(decoder.feed(END), Ok(Some(1)));
+        assert_eq!(decoder.frame(), &[0x09]);
+    }
+}
@@ -0,0 +1,602 @@
+//! Serial interface
+
+use nb;
+#[cfg(feature = "unproven")]
+use void::Void;
+
+#[cfg(feature = "unproven")]
+pub mod buffered;
+#[cfg(feature = "unproven")]
+pub mod flow;
+#[cfg(feature = "unproven")]
+pub mod framing;
+#[cfg(feature = "unproven")]
+pub mod rs485;
+#[cfg(feature = "unproven")]
+pub mod soft;
+
+/// Read half of a serial interface
+///
+/// Some serial interfaces support different data sizes (8 bits, 9 bits, etc.);
+/// This can be encoded in this trait via the `Word` type parameter.
+pub trait Read<Word> {
+    /// Read error
+    type Error;
+
+    /// Reads a single word from the serial interface
+    fn read(&mut self) -> nb::Result<Word, Self::Error>;
+}
+
+/// Write half of a serial interface
+pub trait Write<Word> {
+    /// Write error
+    type Error;
+
+    /// Writes a single word to the serial interface
+    fn write(&mut self, word: Word) -> nb::Result<(), Self::Error>;
+
+    /// Ensures that none of the previously written words are still buffered
+    fn flush(&mut self) -> nb::Result<(), Self::Error>;
+}
+
+/// Number of data bits in a frame
+///
+/// *This enumeration is available if embedded-hal is built with the `"unproven"` feature.*
+#[cfg(feature = "unproven")]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DataBits {
+    /// 5 data bits
+    Five,
+    /// 6 data bits
+    Six,
+    /// 7 data bits
+    Seven,
+    /// 8 data bits
+    Eight,
+    /// 9 data bits
+    Nine,
+}
+
+/// Parity bit
+///
+/// *This enumeration is available if embedded-hal is built with the `"unproven"` feature.*
+#[cfg(feature = "unproven")]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Parity {
+    /// No parity bit
+    None,
+    /// Even number of ones in the data bits plus the parity bit
+    Even,
+    /// Odd number of ones in the data bits plus the parity bit
+    Odd,
+}
+
+/// Number of stop bits
+///
+/// *This enumeration is available if embedded-hal is built with the `"unproven"` feature.*
+#[cfg(feature = "unproven")]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum StopBits {
+    /// 1 stop bit
+    One,
+    /// 1.5 stop bits
+    OneAndHalf,
+    /// 2 stop bits
+    Two,
+}
+
+/// Flow control
+///
+/// *This enumeration is available if embedded-hal is built with the `"unproven"` feature.*
+#[cfg(feature = "unproven")]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FlowControl {
+    /// No flow control
+    None,
+    /// Hardware flow control using the RTS and CTS lines
+    RtsCts,
+    /// Software flow control using the XON and XOFF characters
+    XonXoff,
+}
+
+/// Serial interface configuration
+///
+/// *This struct is available if embedded-hal is built with the `"unproven"` feature.*
+#[cfg(feature = "unproven")]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Config {
+    /// Baud rate, in bits per second
+    pub baud_rate: u32,
+    /// Number of data bits
+    pub data_bits: DataBits,
+    /// Parity bit
+    pub parity: Parity,
+    /// Number of stop bits
+    pub stop_bits: StopBits,
+    /// Flow control
+    pub flow_control: FlowControl,
+}
+
+#[cfg(feature = "unproven")]
+impl Default for Config {
+    /// 115200 baud, 8 data bits, no parity, 1 stop bit, no flow control
+    fn default() -> Self {
+        Config {
+            baud_rate: 115_200,
+            data_bits: DataBits::Eight,
+            parity: Parity::None,
+            stop_bits: StopBits::One,
+            flow_control: FlowControl::None,
+        }
+    }
+}
+
+/// Run time configuration of a serial interface
+///
+/// *This trait is available if embedded-hal is built with the `"unproven"` feature.*
+///
+/// # Examples
+///
+/// Switching a GPS receiver to a higher baud rate
+///
+/// ```
+/// extern crate embedded_hal as hal;
+///
+/// use hal::serial::{Config, Configure};
+///
+/// fn main() {
+///     let mut serial: Serial1 = {
+///         // ..
+/// #       Serial1
+///     };
+///
+///     // omitted: tell the receiver to switch to 115200 baud
+///
+///     let config = Config {
+///         baud_rate: 115_200,
+///         ..Config::default()
+///     };
+///     let actual = serial.configure(&config).unwrap();
+///     assert!(actual > 113_000 && actual < 117_000);
+/// }
+///
+/// # struct Serial1;
+/// # impl Configure for Serial1 {
+/// #     type Error = ();
+/// #     fn configure(&mut self, config: &Config) -> Result<u32, ()> { Ok(config.baud_rate) }
+/// # }
+/// ```
+#[cfg(feature = "unproven")]
+pub trait Configure {
+    /// Configuration error
+    type Error;
+
+    /// Applies `config` and returns the baud rate that was actually achieved
+    ///
+    /// # Contract
+    ///
+    /// - The baud rate may be approximated, e.g. due to clock divider granularity. The returned
+    ///   value is the baud rate that's actually used.
+    /// - All other settings MUST be applied exactly. If a setting is not supported, or the baud
+    ///   rate can't be approximated reasonably, an error MUST be returned and the current
+    ///   configuration left unchanged.
+    /// - Words that are still being transmitted or received while the configuration changes may
+    ///   be corrupted. Use `flush` before reconfiguring to avoid that.
+    fn configure(&mut self, config: &Config) -> Result<u32, Self::Error>;
+}
+
+/// Serial error kind
+///
+/// This represents a common set of serial operation errors. HAL implementations are free to define
+/// more specific or additional error types. However, by providing a mapping to these common serial
+/// errors, generic code can still react to them.
+///
+/// *This enumeration is available if embedded-hal is built with the `"unproven"` feature.*
+#[cfg(feature = "unproven")]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ErrorKind {
+    /// The stop bit of a received word was not detected, e.g. due to a baud rate mismatch
+    Framing,
+    /// The parity of a received word did not match the configured parity
+    Parity,
+    /// A received word was lost because the previous one had not been read yet
+    Overrun,
+    /// Noise was detected on the line while receiving a word
+    Noise,
+    /// A different error occurred. The original error may contain more information.
+    Other,
+}
+
+/// Serial error
+///
+/// Implement this trait for the `Error` type of your serial implementation to let generic code
+/// find out what went wrong.
+///
+/// *This trait is available if embedded-hal is built with the `"unproven"` feature.*
+#[cfg(feature = "unproven")]
+pub trait Error {
+    /// Converts this error into a generic error kind
+    fn kind(&self) -> ErrorKind;
+}
+
+#[cfg(feature = "unproven")]
+impl Error for ErrorKind {
+    fn kind(&self) -> ErrorKind {
+        *self
+    }
+}
+
+#[cfg(feature = "unproven")]
+impl Error for Void {
+    fn kind(&self) -> ErrorKind {
+        match *self {}
+    }
+}
+
+/// Break generation
+///
+/// A break holds the transmit line low for longer than a whole word, including its stop bits.
+/// Protocols like DMX512 and LIN use it to mark the start of a packet.
+///
+/// *This trait is available if embedded-hal is built with the `"unproven"` feature.*
+///
+/// # Examples
+///
+/// Sending a DMX512 packet
+///
+/// ```
+/// extern crate embedded_hal as hal;
+/// #[macro_use(block)]
+/// extern crate nb;
+///
+/// use hal::prelude::*;
+///
+/// fn main() {
+///     let mut serial: Serial1 = {
+///         // ..
+/// #       Serial1
+///     };
+///     let channels = [0u8; 512];
+///
+///     // at 250 kbaud a bit lasts 4 us; DMX512 requires a break of at least 88 us
+///     block!(serial.send_break(22)).unwrap();
+///     block!(serial.flush()).unwrap();
+///
+///     // start code, then the channel levels
+///     block!(serial.write(0)).unwrap();
+///     serial.bwrite_all(&channels).unwrap();
+/// }
+///
+/// # struct Serial1;
+/// # impl hal::serial::Write<u8> for Serial1 {
+/// #     type Error = ();
+/// #     fn write(&mut self, _: u8) -> nb::Result<(), ()> { Ok(()) }
+/// #     fn flush(&mut self) -> nb::Result<(), ()> { Ok(()) }
+/// # }
+/// # impl hal::blocking::serial::write::Default<u8> for Serial1 {}
+/// # impl hal::serial::SendBreak for Serial1 {
+/// #     type Error = ();
+/// #     fn send_break(&mut self, _: u16) -> nb::Result<(), ()> { Ok(()) }
+/// # }
+/// ```
+#[cfg(feature = "unproven")]
+pub trait SendBreak {
+    /// Break error
+    type Error;
+
+    /// Starts sending a break that lasts for at least `bit_times` bit periods
+    ///
+    /// # Contract
+    ///
+    /// - Returns `WouldBlock` until all previously written words have been sent. Once this
+    ///   returns `Ok` the break is in progress; use `flush` to wait until it's over.
+    /// - The break may be longer than requested, e.g. if the hardware only supports a fixed break
+    ///   length, but an error MUST be returned if the requested length can't be reached.
+    fn send_break(&mut self, bit_times: u16) -> nb::Result<(), Self::Error>;
+}
+
+/// Event detected on the receive line
+///
+/// *This enumeration is available if embedded-hal is built with the `"unproven"` feature.*
+#[cfg(feature = "unproven")]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LineEvent {
+    /// The line was held low for longer than a whole word
+    Break,
+    /// The line has been idle for at least one word time after a word was received
+    Idle,
+}
+
+/// Break and idle line detection
+///
+/// *This trait is available if embedded-hal is built with the `"unproven"` feature.*
+#[cfg(feature = "unproven")]
+pub trait LineEvents {
+    /// Line event error
+    type Error;
+
+    /// Returns the oldest line event that hasn't been returned yet
+    ///
+    /// # Contract
+    ///
+    /// - Returns `WouldBlock` if no event has been detected since the last call.
+    /// - A received break MUST be reported as `LineEvent::Break`; it MUST NOT also be reported as
+    ///   a framing error by `Read::read`, nor as a received zero word.
+    /// - `LineEvent::Idle` is reported once per idle period, i.e. the line has to receive another
+    ///   word before the next `Idle` event.
+    fn line_event(&mut self) -> nb::Result<LineEvent, Self::Error>;
+}
+
+/// Non-blocking bulk write
+///
+/// Implement this trait for serial interfaces that can transfer a whole buffer on their own, e.g.
+/// using DMA or by refilling a FIFO from an interrupt handler. Serial interfaces that only
+/// implement [`Write`](trait.Write.html) can be used wherever this trait is required through the
+/// [`WordByWord`](struct.WordByWord.html) adapter.
+///
+/// The serial interface takes ownership of the buffer for the duration of the transfer and hands
+/// it back once the transfer is over, so the buffer can be refilled and reused. The buffer has a
+/// `'static` lifetime because the transfer keeps going after `start_write` returns; a borrowed
+/// buffer could be freed or reused while the hardware still reads from it.
+///
+/// *This trait is available if embedded-hal is built with the `"unproven"` feature.*
+///
+/// # Examples
+///
+/// ```
+/// extern crate embedded_hal as hal;
+/// #[macro_use(block)]
+/// extern crate nb;
+///
+/// use hal::serial::WriteBuffer;
+///
+/// fn main() {
+///     let mut serial: Serial1 = {
+///         // ..
+/// #       Serial1(None)
+///     };
+///     let buffer: &'static mut [u8] = {
+///         // e.g. from a `singleton!` style macro
+/// #       Box::leak(Box::new([0; 6]))
+///     };
+///
+///     buffer.copy_from_slice(b"hello\n");
+///     serial.start_write(buffer);
+///
+///     // omitted: do something else while the words are being sent
+///
+///     let buffer = block!(serial.poll_write()).unwrap();
+///
+///     // the buffer can be reused once the transfer is over
+///     buffer.copy_from_slice(b"world\n");
+///     serial.start_write(buffer);
+/// }
+///
+/// # struct Serial1(Option<&'static mut [u8]>);
+/// # impl WriteBuffer<u8> for Serial1 {
+/// #     type Error = ();
+/// #     fn start_write(&mut self, buffer: &'static mut [u8]) { self.0 = Some(buffer); }
+/// #     fn poll_write(&mut self) -> nb::Result<&'static mut [u8], ()> {
+/// #         Ok(self.0.take().unwrap())
+/// #     }
+/// # }
+/// ```
+#[cfg(feature = "unproven")]
+pub trait WriteBuffer<Word> {
+    /// Write error
+    type Error;
+
+    /// Starts writing all the words in `buffer`
+    ///
+    /// Errors are reported by `poll_write`.
+    ///
+    /// # Contract
+    ///
+    /// - This method MUST only be called when no transfer is in progress, i.e. before the first
+    ///   transfer or after `poll_write` has handed back the buffer of the previous one.
+    ///   Implementers are suggested to panic otherwise to signal a programmer error.
+    fn start_write(&mut self, buffer: &'static mut [Word]);
+
+    /// Checks whether the current transfer is over
+    ///
+    /// Returns `WouldBlock` while the transfer is in progress and hands back the buffer once every
+    /// word has been written. Like with `Write::write`, written words may still be in transit;
+    /// use `Write::flush` to wait until they have been sent.
+    ///
+    /// If the transfer fails the error is returned and the rest of the buffer is not written; the
+    /// next call then hands back the buffer.
+    ///
+    /// # Contract
+    ///
+    /// - The behavior of calling this method while no transfer is in progress is UNSPECIFIED.
+    ///   Implementers are suggested to panic on this scenario to signal a programmer error.
+    fn poll_write(&mut self) -> nb::Result<&'static mut [Word], Self::Error>;
+}
+
+/// [`WriteBuffer`](trait.WriteBuffer.html) implementation that writes one word at a time
+///
+/// Each call to `poll_write` writes as many words as the serial interface accepts without
+/// blocking. Errors of the transfer are reported by whichever of `poll_write`, `write` and `flush`
+/// runs into them.
+///
+/// *This struct is available if embedded-hal is built with the `"unproven"` feature.*
+#[cfg(feature = "unproven")]
+pub struct WordByWord<S, Word>
+where
+    Word: 'static,
+{
+    serial: S,
+    // buffer of the current transfer
+    buffer: Option<&'static mut [Word]>,
+    // number of words of the buffer that have been written
+    written: usize,
+    // the current transfer failed
+    failed: bool,
+}
+
+#[cfg(feature = "unproven")]
+impl<S, Word> WordByWord<S, Word>
+where
+    S: Write<Word>,
+    Word: Copy + 'static,
+{
+    /// Wraps `serial`
+    pub fn new(serial: S) -> Self {
+        WordByWord {
+            serial,
+            buffer: None,
+            written: 0,
+            failed: false,
+        }
+    }
+
+    /// Releases the serial interface, and the buffer of the current transfer, if any
+    ///
+    /// The words of the current transfer that haven't been written yet are dropped.
+    pub fn free(self) -> (S, Option<&'static mut [Word]>) {
+        (self.serial, self.buffer)
+    }
+
+    /// Writes the words of the current transfer that the serial interface accepts
+    ///
+    /// Returns `Ok` once there's nothing left to write.
+    fn advance(&mut self) -> nb::Result<(), S::Error> {
+        let buffer = match self.buffer {
+            Some(ref buffer) if !self.failed => buffer,
+            _ => return Ok(()),
+        };
+
+        while let Some(&word) = buffer.get(self.written) {
+            if let Err(e) = self.serial.write(word) {
+                if let nb::Error::Other(_) = e {
+                    self.failed = true;
+                }
+                return Err(e);
+            }
+            self.written += 1;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "unproven")]
+impl<S, Word> WriteBuffer<Word> for WordByWord<S, Word>
+where
+    S: Write<Word>,
+    Word: Copy + 'static,
+{
+    type Error = S::Error;
+
+    fn start_write(&mut self, buffer: &'static mut [Word]) {
+        assert!(self.buffer.is_none(), "transfer already in progress");
+
+        self.buffer = Some(buffer);
+        self.written = 0;
+        self.failed = false;
+    }
+
+    fn poll_write(&mut self) -> nb::Result<&'static mut [Word], S::Error> {
+        assert!(self.buffer.is_some(), "no transfer in progress");
+
+        self.advance()?;
+        Ok(self.buffer.take().unwrap())
+    }
+}
+
+#[cfg(feature = "unproven")]
+impl<S, Word> Write<Word> for WordByWord<S, Word>
+where
+    S: Write<Word>,
+    Word: Copy + 'static,
+{
+    type Error = S::Error;
+
+    /// Writes `word` once `poll_write` has handed back the buffer of the current transfer
+    fn write(&mut self, word: Word) -> nb::Result<(), S::Error> {
+        self.advance()?;
+        if self.buffer.is_some() {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        self.serial.write(word)
+    }
+
+    /// Waits until `poll_write` has handed back the buffer of the current transfer and the
+    /// written words have been sent
+    fn flush(&mut self) -> nb::Result<(), S::Error> {
+        self.advance()?;
+        if self.buffer.is_some() {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        self.serial.flush()
+    }
+}
+
+#[cfg(all(test, feature = "unproven"))]
+mod tests {
+    use core::ptr;
+
+    use super::*;
+
+    /// Accepts `room` words at a time
+    struct Serial {
+        sent: [u8; 8],
+        len: usize,
+        room: usize,
+    }
+
+    impl Write<u8> for Serial {
+        type Error = ();
+
+        fn write(&mut self, word: u8) -> nb::Result<(), ()> {
+            if self.room == 0 {
+                return Err(nb::Error::WouldBlock);
+            }
+            self.room -= 1;
+            self.sent[self.len] = word;
+            self.len += 1;
+            Ok(())
+        }
+
+        fn flush(&mut self) -> nb::Result<(), ()> {
+            Ok(())
+        }
+    }
+
+    static mut DATA: [u8; 5] = [1, 2, 3, 4, 5];
+
+    #[test]
+    fn word_by_word() {
+        // NOTE(unsafe) this is the only test that uses `DATA`
+        let data: &'static mut [u8] = unsafe { &mut *ptr::addr_of_mut!(DATA) };
+        let mut serial = WordByWord::new(Serial {
+            sent: [0; 8],
+            len: 0,
+            room: 2,
+        });
+
+        serial.start_write(data);
+        assert_eq!(serial.poll_write(), Err(nb::Error::WouldBlock));
+        serial.serial.room = 2;
+        assert_eq!(serial.poll_write(), Err(nb::Error::WouldBlock));
+        serial.serial.room = 2;
+        // the transfer is over but its buffer hasn't been handed back yet
+        assert_eq!(serial.write(6), Err(nb::Error::WouldBlock));
+
+        let data = serial.poll_write().unwrap();
+        assert_eq!(data, &[1, 2, 3, 4, 5]);
+        assert_eq!(serial.write(6), Ok(()));
+
+        // the buffer can be reused
+        data[0] = 7;
+        serial.start_write(data);
+        serial.serial.room = 0;
+        assert_eq!(serial.poll_write(), Err(nb::Error::WouldBlock));
+
+        let (serial, data) = serial.free();
+        assert_eq!(data.map(|data| data[0]), Some(7));
+        assert_eq!(&serial.sent[..serial.len], &[1, 2, 3, 4, 5, 6]);
+    }
+}
@@ -0,0 +1,318 @@
+//! RS-485 half-duplex support
+//!
+//! RS-485 transceivers have a driver enable (DE) input that must be asserted while the UART is
+//! transmitting and released afterwards so that other nodes can use the bus. [`Rs485`] wraps a
+//! serial interface and a DE pin and toggles the pin automatically:
+//!
+//! - DE is asserted (driven high) before the first word is written.
+//! - DE is released (driven low) once `flush` reports that all words have been sent.
+//!
+//! The transceiver's receiver is often left enabled while transmitting, in which case every
+//! transmitted word is received back. [`Rs485`] can optionally discard that local echo.
+//!
+//! *This module is available if embedded-hal is built with the `"unproven"` feature.*
+//!
+//! [`Rs485`]: struct.Rs485.html
+//!
+//! ```
+//! extern crate embedded_hal as hal;
+//! #[macro_use(block)]
+//! extern crate nb;
+//!
+//! use hal::prelude::*;
+//! use hal::serial::rs485::Rs485;
+//!
+//! fn main() {
+//!     let (serial, de): (Serial1, DePin) = {
+//!         // ..
+//! #       (Serial1, DePin)
+//!     };
+//!
+//!     let mut bus = Rs485::new(serial, de);
+//!     bus.set_discard_echo(true);
+//!
+//!     bus.bwrite_all(b"request").unwrap();
+//!     // releases the bus once the last stop bit has been sent
+//!     bus.bflush().unwrap();
+//!
+//!     let response = block!(bus.read()).unwrap();
+//! }
+//!
+//! # struct Serial1;
+//! # impl hal::serial::Write<u8> for Serial1 {
+//! #     type Error = ();
+//! #     fn write(&mut self, _: u8) -> nb::Result<(), ()> { Ok(()) }
+//! #     fn flush(&mut self) -> nb::Result<(), ()> { Ok(()) }
+//! # }
+//! # impl hal::serial::Read<u8> for Serial1 {
+//! #     type Error = ();
+//! #     fn read(&mut self) -> nb::Result<u8, ()> { Ok(0) }
+//! # }
+//! # struct DePin;
+//! # impl hal::digital::v2::OutputPin for DePin {
+//! #     type Error = ();
+//! #     fn set_low(&mut self) -> Result<(), ()> { Ok(()) }
+//! #     fn set_high(&mut self) -> Result<(), ()> { Ok(()) }
+//! # }
+//! ```
+
+use nb;
+
+use digital::v2::OutputPin;
+use serial::{ErrorKind, Read, Write};
+
+/// RS-485 error
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Error<E, P> {
+    /// Serial interface error
+    Serial(E),
+    /// Driver enable pin error
+    Pin(P),
+}
+
+impl<E, P> ::serial::Error for Error<E, P>
+where
+    E: ::serial::Error,
+{
+    fn kind(&self) -> ErrorKind {
+        match *self {
+            Error::Serial(ref e) => e.kind(),
+            Error::Pin(_) => ErrorKind::Other,
+        }
+    }
+}
+
+/// Serial interface on an RS-485 bus
+pub struct Rs485<S, DE> {
+    serial: S,
+    de: DE,
+    transmitting: bool,
+    discard_echo: bool,
+    pending_echo: usize,
+}
+
+impl<S, DE> Rs485<S, DE>
+where
+    DE: OutputPin,
+{
+    /// Wraps `serial`, using `de` as the driver enable pin
+    ///
+    /// `de` must be low, i.e. the driver must be disabled.
+    pub fn new(serial: S, de: DE) -> Self {
+        Rs485 {
+            serial,
+            de,
+            transmitting: false,
+            discard_echo: false,
+            pending_echo: 0,
+        }
+    }
+
+    /// Enables or disables discarding the local echo of transmitted words
+    ///
+    /// While enabled, one received word is dropped for each word written.
+    pub fn set_discard_echo(&mut self, enabled: bool) {
+        self.discard_echo = enabled;
+        if !enabled {
+            self.pending_echo = 0;
+        }
+    }
+
+    /// Returns `true` if the driver is enabled
+    pub fn is_transmitting(&self) -> bool {
+        self.transmitting
+    }
+
+    /// Releases the serial interface and the driver enable pin
+    pub fn free(self) -> (S, DE) {
+        (self.serial, self.de)
+    }
+}
+
+impl<S, DE> Write<u8> for Rs485<S, DE>
+where
+    S: Write<u8>,
+    DE: OutputPin,
+{
+    type Error = Error<S::Error, DE::Error>;
+
+    fn write(&mut self, word: u8) -> nb::Result<(), Self::Error> {
+        if !self.transmitting {
+            self.de
+                .set_high()
+                .map_err(|e| nb::Error::Other(Error::Pin(e)))?;
+            self.transmitting = true;
+        }
+
+        match self.serial.write(word) {
+            Ok(()) => {
+                if self.discard_echo {
+                    self.pending_echo += 1;
+                }
+                Ok(())
+            }
+            Err(nb::Error::WouldBlock) => Err(nb::Error::WouldBlock),
+            Err(nb::Error::Other(e)) => Err(nb::Error::Other(Error::Serial(e))),
+        }
+    }
+
+    /// Waits until all words have been sent and releases the bus
+    ///
+    /// The wrapped serial interface's `flush` must only return `Ok` once the stop bit of the last
+    /// word has left the shift register (e.g. "transmission complete" rather than "transmit
+    /// buffer empty"), otherwise the last word will be cut off.
+    fn flush(&mut self) -> nb::Result<(), Self::Error> {
+        match self.serial.flush() {
+            Ok(()) => {}
+            Err(nb::Error::WouldBlock) => return Err(nb::Error::WouldBlock),
+            Err(nb::Error::Other(e)) => return Err(nb::Error::Other(Error::Serial(e))),
+        }
+
+        if self.transmitting {
+            self.de
+                .set_low()
+                .map_err(|e| nb::Error::Other(Error::Pin(e)))?;
+            self.transmitting = false;
+        }
+
+        Ok(())
+    }
+}
+
+impl<S, DE> ::blocking::serial::write::Default<u8> for Rs485<S, DE>
+where
+    S: Write<u8>,
+    DE: OutputPin,
+{
+}
+
+impl<S, DE> Read<u8> for Rs485<S, DE>
+where
+    S: Read<u8>,
+    DE: OutputPin,
+{
+    type Error = Error<S::Error, DE::Error>;
+
+    fn read(&mut self) -> nb::Result<u8, Self::Error> {
+        loop {
+            let word = match self.serial.read() {
+                Ok(word) => word,
+                Err(nb::Error::WouldBlock) => return Err(nb::Error::WouldBlock),
+                Err(nb::Error::Other(e)) => return Err(nb::Error::Other(Error::Serial(e))),
+            };
+
+            if self.pending_echo == 0 {
+                return Ok(word);
+            }
+
+            self.pending_echo -= 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::cell::Cell;
+
+    /// UART that checks DE on every write and receives `incoming`
+    struct Serial<'a> {
+        de: &'a Cell<bool>,
+        incoming: &'a [u8],
+        busy: bool,
+    }
+
+    impl<'a> Write<u8> for Serial<'a> {
+        type Error = ();
+
+        fn write(&mut self, _: u8) -> nb::Result<(), ()> {
+            assert!(self.de.get(), "DE must be asserted while transmitting");
+            self.busy = true;
+            Ok(())
+        }
+
+        fn flush(&mut self) -> nb::Result<(), ()> {
+            if self.busy {
+                // transmission completes on the next poll
+                self.busy = false;
+                return Err(nb::Error::WouldBlock);
+            }
+            Ok(())
+        }
+    }
+
+    impl<'a> Read<u8> for Serial<'a> {
+        type Error = ();
+
+        fn read(&mut self) -> nb::Result<u8, ()> {
+            match self.incoming.split_first() {
+                Some((&first, rest)) => {
+                    self.incoming = rest;
+                    Ok(first)
+                }
+                None => Err(nb::Error::WouldBlock),
+            }
+        }
+    }
+
+    struct De<'a>(&'a Cell<bool>);
+
+    impl<'a> OutputPin for De<'a> {
+        type Error = ();
+
+        fn set_low(&mut self) -> Result<(), ()> {
+            self.0.set(false);
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), ()> {
+            self.0.set(true);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn driver_enable() {
+        let de = Cell::new(false);
+        let serial = Serial {
+            de: &de,
+            incoming: &[1],
+            busy: false,
+        };
+        let mut bus = Rs485::new(serial, De(&de));
+
+        block!(bus.write(1)).unwrap();
+        block!(bus.write(2)).unwrap();
+        assert!(de.get());
+
+        assert_eq!(bus.flush(), Err(nb::Error::WouldBlock));
+        assert!(de.get(), "DE released before transmission complete");
+        assert_eq!(bus.flush(), Ok(()));
+        assert!(!de.get());
+        assert!(!bus.is_transmitting());
+
+        // local echo is received as is
+        assert_eq!(bus.read(), Ok(1));
+    }
+
+    #[test]
+    fn discard_echo() {
+        let de = Cell::new(false);
+        let serial = Serial {
+            de: &de,
+            // echo of the request followed by the response of another node
+            incoming: &[1, 2, 3],
+            busy: false,
+        };
+        let mut bus = Rs485::new(serial, De(&de));
+        bus.set_discard_echo(true);
+
+        block!(bus.write(1)).unwrap();
+        block!(bus.write(2)).unwrap();
+        block!(bus.flush()).unwrap();
+
+        assert_eq!(bus.read(), Ok(3));
+        assert_eq!(bus.read(), Err(nb::Error::WouldBlock));
+    }
+}
@@ -0,0 +1,519 @@
+//! Bit banged serial interface
+//!
+//! [`SoftTx`] and [`SoftRx`] implement the serial traits on top of plain digital pins, for when a
+//! device runs out of UART peripherals. Both use 8 data bits, 1 stop bit and either no, even or
+//! odd parity.
+//!
+//! The bit clock is provided by a periodic [`CountDown`] timer that the caller has already
+//! started:
+//!
+//! - The transmitter's timer must tick at the baud rate.
+//! - The receiver's timer must tick at [`OVERSAMPLING`] times the baud rate. The receive line is
+//!   sampled on every tick to detect the falling edge of the start bit; from there on every bit is
+//!   sampled near its middle.
+//!
+//! Timing is only as accurate as the calls to `read`, `write` and `flush`: they must be polled
+//! continuously, e.g. with `block!`, while a word is being transferred, and the receiver must
+//! already be polled when the start bit arrives. Words that arrive while the receiver isn't polled
+//! are lost or garbled.
+//!
+//! *This module is available if embedded-hal is built with the `"unproven"` feature.*
+//!
+//! [`SoftTx`]: struct.SoftTx.html
+//! [`SoftRx`]: struct.SoftRx.html
+//! [`CountDown`]: ../../timer/trait.CountDown.html
+//! [`OVERSAMPLING`]: constant.OVERSAMPLING.html
+//!
+//! ``` no_run
+//! extern crate embedded_hal as hal;
+//! #[macro_use(block)]
+//! extern crate nb;
+//!
+//! use hal::prelude::*;
+//! use hal::serial::Parity;
+//! use hal::serial::soft::{SoftRx, SoftTx, OVERSAMPLING};
+//!
+//! fn main() {
+//!     let (tx_pin, rx_pin, mut tx_timer, mut rx_timer) = {
+//!         // ..
+//! #       (Pin, Pin, Timer, Timer)
+//!     };
+//!
+//!     // 9600 baud, 8E1
+//!     tx_timer.start(Hertz(9_600));
+//!     rx_timer.start(Hertz(9_600 * u32::from(OVERSAMPLING)));
+//!     let mut tx = SoftTx::new(tx_pin, tx_timer, Parity::Even).unwrap();
+//!     let mut rx = SoftRx::new(rx_pin, rx_timer, Parity::Even);
+//!
+//!     tx.bwrite_all(b"AT\r").unwrap();
+//!     let reply = block!(rx.read()).unwrap();
+//! }
+//!
+//! # extern crate void;
+//! # struct Hertz(u32);
+//! # struct Pin;
+//! # impl hal::digital::v2::OutputPin for Pin {
+//! #     type Error = void::Void;
+//! #     fn set_low(&mut self) -> Result<(), void::Void> { Ok(()) }
+//! #     fn set_high(&mut self) -> Result<(), void::Void> { Ok(()) }
+//! # }
+//! # impl hal::digital::v2::InputPin for Pin {
+//! #     type Error = void::Void;
+//! #     fn is_high(&self) -> Result<bool, void::Void> { Ok(false) }
+//! #     fn is_low(&self) -> Result<bool, void::Void> { Ok(true) }
+//! # }
+//! # struct Timer;
+//! # impl hal::timer::CountDown for Timer {
+//! #     type Time = Hertz;
+//! #     fn start<T>(&mut self, _: T) where T: Into<Hertz> {}
+//! #     fn wait(&mut self) -> nb::Result<(), void::Void> { Ok(()) }
+//! # }
+//! # impl hal::timer::Periodic for Timer {}
+//! ```
+
+use nb;
+
+use digital::v2::{InputPin, OutputPin};
+use serial::{self, ErrorKind, Parity, Read, Write};
+use timer::{CountDown, Periodic};
+
+/// Number of receiver timer ticks per bit
+pub const OVERSAMPLING: u8 = 4;
+
+/// Software serial error
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Error<P> {
+    /// The stop bit of a received word was low
+    Framing,
+    /// The parity bit of a received word was wrong
+    Parity,
+    /// Pin error
+    Pin(P),
+}
+
+impl<P> serial::Error for Error<P> {
+    fn kind(&self) -> ErrorKind {
+        match *self {
+            Error::Framing => ErrorKind::Framing,
+            Error::Parity => ErrorKind::Parity,
+            Error::Pin(_) => ErrorKind::Other,
+        }
+    }
+}
+
+/// Returns the parity bit of `word`
+fn parity_bit(word: u8, parity: Parity) -> Option<bool> {
+    let odd_ones = word.count_ones() % 2 == 1;
+
+    match parity {
+        Parity::None => None,
+        Parity::Even => Some(odd_ones),
+        Parity::Odd => Some(!odd_ones),
+    }
+}
+
+/// Number of bits in a frame, including the start bit
+fn frame_bits(parity: Parity) -> u8 {
+    match parity {
+        Parity::None => 10,
+        Parity::Even | Parity::Odd => 11,
+    }
+}
+
+/// Bit banged transmitter
+pub struct SoftTx<TX, T> {
+    pin: TX,
+    timer: T,
+    parity: Parity,
+    // bits left to send, LSB first
+    frame: u16,
+    // ticks left until the frame, including its stop bit, has been sent
+    remaining: u8,
+}
+
+impl<TX, T> SoftTx<TX, T>
+where
+    TX: OutputPin,
+    T: CountDown + Periodic,
+{
+    /// Creates a transmitter that sends through `pin`, using `timer` as the bit clock
+    ///
+    /// `pin` is driven high, i.e. idle, right away.
+    pub fn new(mut pin: TX, timer: T, parity: Parity) -> Result<Self, TX::Error> {
+        pin.set_high()?;
+
+        Ok(SoftTx {
+            pin,
+            timer,
+            parity,
+            frame: 0,
+            remaining: 0,
+        })
+    }
+
+    /// Releases the pin and the timer
+    pub fn free(self) -> (TX, T) {
+        (self.pin, self.timer)
+    }
+
+    fn poll(&mut self) -> nb::Result<(), Error<TX::Error>> {
+        while self.remaining != 0 {
+            match self.timer.wait() {
+                Ok(()) => {}
+                Err(nb::Error::WouldBlock) => return Err(nb::Error::WouldBlock),
+                Err(nb::Error::Other(e)) => match e {},
+            }
+
+            self.remaining -= 1;
+            if self.remaining == 0 {
+                // the stop bit has lasted a whole bit period
+                break;
+            }
+
+            let high = self.frame & 1 != 0;
+            self.frame >>= 1;
+            if high {
+                self.pin.set_high().map_err(Error::Pin)?;
+            } else {
+                self.pin.set_low().map_err(Error::Pin)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<TX, T> Write<u8> for SoftTx<TX, T>
+where
+    TX: OutputPin,
+    T: CountDown + Periodic,
+{
+    type Error = Error<TX::Error>;
+
+    /// Starts sending `word` once the previous word has been sent
+    fn write(&mut self, word: u8) -> nb::Result<(), Self::Error> {
+        self.poll()?;
+
+        // skip a tick that may still be pending so that the start bit lasts a whole bit period
+        while self.timer.wait().is_ok() {}
+
+        let bits = frame_bits(self.parity);
+        // start bit (low), data bits, parity bit, stop bit (high)
+        let mut frame = u16::from(word) << 1 | 1 << (bits - 1);
+        if let Some(true) = parity_bit(word, self.parity) {
+            frame |= 1 << 9;
+        }
+
+        self.frame = frame;
+        // one more tick to end the stop bit
+        self.remaining = bits + 1;
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Self::Error> {
+        self.poll()
+    }
+}
+
+impl<TX, T> ::blocking::serial::write::Default<u8> for SoftTx<TX, T>
+where
+    TX: OutputPin,
+    T: CountDown + Periodic,
+{
+}
+
+/// Bit banged receiver
+pub struct SoftRx<RX, T> {
+    pin: RX,
+    timer: T,
+    parity: Parity,
+    // bits received so far, LSB first, starting with the start bit
+    frame: u16,
+    // number of bits received so far; zero while waiting for a start bit
+    bits: u8,
+    // ticks left until the next bit is sampled
+    ticks: u8,
+}
+
+impl<RX, T> SoftRx<RX, T>
+where
+    RX: InputPin,
+    T: CountDown + Periodic,
+{
+    /// Creates a receiver that listens on `pin`, using `timer` as the bit clock
+    pub fn new(pin: RX, timer: T, parity: Parity) -> Self {
+        SoftRx {
+            pin,
+            timer,
+            parity,
+            frame: 0,
+            bits: 0,
+            ticks: 0,
+        }
+    }
+
+    /// Releases the pin and the timer
+    pub fn free(self) -> (RX, T) {
+        (self.pin, self.timer)
+    }
+
+    /// Processes one timer tick
+    fn tick(&mut self) -> Result<Option<u8>, Error<RX::Error>> {
+        if self.ticks > 1 {
+            self.ticks -= 1;
+            return Ok(None);
+        }
+
+        let high = self.pin.is_high().map_err(Error::Pin)?;
+
+        if self.bits == 0 {
+            if !high {
+                // falling edge; sample the start bit in its middle
+                self.frame = 0;
+                self.bits = 1;
+                self.ticks = OVERSAMPLING / 2;
+            }
+            return Ok(None);
+        }
+
+        self.frame |= u16::from(high) << (self.bits - 1);
+        self.bits += 1;
+        self.ticks = OVERSAMPLING;
+
+        if self.bits == 2 && high {
+            // the start bit was a glitch
+            self.bits = 0;
+            self.ticks = 0;
+            return Ok(None);
+        }
+
+        let bits = frame_bits(self.parity);
+        if self.bits <= bits {
+            return Ok(None);
+        }
+
+        // return as soon as the stop bit has been sampled, to catch the next start bit
+        self.bits = 0;
+        self.ticks = 0;
+
+        let word = (self.frame >> 1) as u8;
+        if self.frame & 1 << (bits - 1) == 0 {
+            return Err(Error::Framing);
+        }
+        if let Some(parity) = parity_bit(word, self.parity) {
+            if (self.frame & 1 << 9 != 0) != parity {
+                return Err(Error::Parity);
+            }
+        }
+
+        Ok(Some(word))
+    }
+}
+
+impl<RX, T> Read<u8> for SoftRx<RX, T>
+where
+    RX: InputPin,
+    T: CountDown + Periodic,
+{
+    type Error = Error<RX::Error>;
+
+    fn read(&mut self) -> nb::Result<u8, Self::Error> {
+        loop {
+            match self.timer.wait() {
+                Ok(()) => {}
+                Err(nb::Error::WouldBlock) => return Err(nb::Error::WouldBlock),
+                Err(nb::Error::Other(e)) => match e {},
+            }
+
+            if let Some(word) = self.tick()? {
+                return Ok(word);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::cell::Cell;
+    use void::Void;
+
+    /// Timer that ticks on every other call to `wait`, up to `limit` ticks
+    struct Timer<'a> {
+        ticks: &'a Cell<usize>,
+        limit: usize,
+        every_other: bool,
+        skip: bool,
+    }
+
+    impl<'a> Timer<'a> {
+        fn new(ticks: &'a Cell<usize>, limit: usize, every_other: bool) -> Self {
+            Timer {
+                ticks,
+                limit,
+                every_other,
+                skip: false,
+            }
+        }
+    }
+
+    impl<'a> CountDown for Timer<'a> {
+        type Time = ();
+
+        fn start<T>(&mut self, _: T)
+        where
+            T: Into<()>,
+        {
+        }
+
+        fn wait(&mut self) -> nb::Result<(), Void> {
+            if self.ticks.get() == self.limit || self.skip {
+                self.skip = false;
+                return Err(nb::Error::WouldBlock);
+            }
+            self.skip = self.every_other;
+            self.ticks.set(self.ticks.get() + 1);
+            Ok(())
+        }
+    }
+
+    impl<'a> Periodic for Timer<'a> {}
+
+    /// Records the level set on every tick
+    struct TxPin<'a> {
+        ticks: &'a Cell<usize>,
+        levels: [Option<bool>; 32],
+    }
+
+    impl<'a> TxPin<'a> {
+        fn set(&mut self, high: bool) -> Result<(), ()> {
+            let tick = self.ticks.get();
+            assert_eq!(self.levels[tick], None, "pin set twice during a tick");
+            self.levels[tick] = Some(high);
+            Ok(())
+        }
+    }
+
+    impl<'a> OutputPin for TxPin<'a> {
+        type Error = ();
+
+        fn set_low(&mut self) -> Result<(), ()> {
+            self.set(false)
+        }
+
+        fn set_high(&mut self) -> Result<(), ()> {
+            self.set(true)
+        }
+    }
+
+    /// Plays back `levels`, one bit per tick
+    struct RxPin<'a> {
+        ticks: &'a Cell<usize>,
+        levels: &'a [bool],
+    }
+
+    impl<'a> InputPin for RxPin<'a> {
+        type Error = ();
+
+        fn is_high(&self) -> Result<bool, ()> {
+            Ok(self.levels[self.ticks.get() - 1])
+        }
+
+        fn is_low(&self) -> Result<bool, ()> {
+            self.is_high().map(|high| !high)
+        }
+    }
+
+    fn transmit(word: u8, parity: Parity) -> [Option<bool>; 32] {
+        let ticks = Cell::new(0);
+        let pin = TxPin {
+            ticks: &ticks,
+            levels: [None; 32],
+        };
+        let mut tx = SoftTx::new(pin, Timer::new(&ticks, 32, true), parity).unwrap();
+
+        block!(tx.write(word)).unwrap();
+        block!(tx.write(word)).unwrap();
+        block!(tx.flush()).unwrap();
+
+        tx.free().0.levels
+    }
+
+    #[test]
+    fn transmit_8n1() {
+        let levels = transmit(0x35, Parity::None);
+
+        // idle, a skipped tick, then 0x35 twice; the second frame starts a bit period after the
+        // first stop bit
+        let h = Some(true);
+        let l = Some(false);
+        assert_eq!(
+            &levels[..14],
+            &[h, None, l, h, l, h, l, h, h, l, l, h, None, l]
+        );
+    }
+
+    #[test]
+    fn transmit_8e1_8o1() {
+        let h = Some(true);
+        let l = Some(false);
+
+        // 0x35 has an even number of ones
+        let levels = transmit(0x35, Parity::Even);
+        assert_eq!(&levels[2..13], &[l, h, l, h, l, h, h, l, l, l, h]);
+        let levels = transmit(0x35, Parity::Odd);
+        assert_eq!(&levels[2..13], &[l, h, l, h, l, h, h, l, l, h, h]);
+    }
+
+    /// Builds the line levels of `frames`, each one a `u16` with the given number of bits, LSB
+    /// first, starting with the start bit
+    fn line(frames: &[(u16, u8)], levels: &mut [bool; 192]) -> usize {
+        let mut len = 0;
+        for &(frame, bits) in frames {
+            // some idle time, not a multiple of the bit period
+            len += 3;
+            for bit in 0..bits {
+                for _ in 0..OVERSAMPLING {
+                    levels[len] = frame & 1 << bit != 0;
+                    len += 1;
+                }
+            }
+        }
+
+        len
+    }
+
+    #[test]
+    fn receive() {
+        let mut levels = [true; 192];
+        // 0x35 with 8N1, 0x35 with 8E1, framing error, 0xca with a parity error
+        let len = line(
+            &[
+                (0x35 << 1 | 1 << 9, 10),
+                (0x35 << 1 | 1 << 10, 11),
+                (0x35 << 1, 11),
+                (0xca << 1 | 1 << 9 | 1 << 10, 11),
+            ],
+            &mut levels,
+        );
+        // a glitch, shorter than half a bit, before the third frame
+        levels[43 + 47] = false;
+
+        let ticks = Cell::new(0);
+        let pin = RxPin {
+            ticks: &ticks,
+            levels: &levels[..len],
+        };
+        let mut rx = SoftRx::new(pin, Timer::new(&ticks, 43, false), Parity::None);
+        assert_eq!(rx.read(), Ok(0x35));
+
+        let (pin, _) = rx.free();
+        let mut rx = SoftRx::new(pin, Timer::new(&ticks, len, false), Parity::Even);
+        assert_eq!(rx.read(), Ok(0x35));
+        assert_eq!(rx.read(), Err(nb::Error::Other(Error::Framing)));
+        assert_eq!(rx.read(), Err(nb::Error::Other(Error::Parity)));
+        assert_eq!(rx.read(), Err(nb::Error::WouldBlock));
+    }
+}
@@ -0,0 +1,213 @@
+//! Time units
+//!
+//! The `Time` associated types of traits like `timer::CountDown`, `Pwm`, `Capture` and
+//! `watchdog::WatchdogEnable` are defined by each HAL implementation. The units in this module
+//! give generic code a common vocabulary: a HAL implements `From<Milliseconds>` (or the
+//! [`DurationUnits`] / [`FrequencyUnits`] bundles) for its `Time` types and a driver bounds on
+//! that to pass standard units into these traits.
+//!
+//! Converting a coarse unit into a finer one can overflow, so those conversions are implemented
+//! with `TryFrom` rather than `From`.
+//!
+//! [`U32Ext`] is not part of the prelude because many HAL implementations already define a trait
+//! with the same methods; import it explicitly.
+//!
+//! *This module is available if embedded-hal is built with the `"unproven"` feature.*
+//!
+//! [`DurationUnits`]: trait.DurationUnits.html
+//! [`FrequencyUnits`]: trait.FrequencyUnits.html
+//! [`U32Ext`]: trait.U32Ext.html
+//!
+//! ```
+//! extern crate embedded_hal as hal;
+//! #[macro_use(block)]
+//! extern crate nb;
+//!
+//! use hal::time::{Milliseconds, U32Ext};
+//! use hal::timer::CountDown;
+//!
+//! /// Generic driver code
+//! fn debounce<T>(timer: &mut T)
+//! where
+//!     T: CountDown,
+//!     T::Time: From<Milliseconds>,
+//! {
+//!     timer.start(10.ms());
+//!     block!(timer.wait()).unwrap();
+//! }
+//!
+//! fn main() {
+//!     let mut timer: Timer6 = {
+//!         // ..
+//! #       Timer6
+//!     };
+//!
+//!     debounce(&mut timer);
+//! }
+//!
+//! # extern crate void;
+//! # struct Ticks(u32);
+//! # impl From<Milliseconds> for Ticks {
+//! #     fn from(ms: Milliseconds) -> Ticks { Ticks(ms.0 * 32) }
+//! # }
+//! # struct Timer6;
+//! # impl CountDown for Timer6 {
+//! #     type Time = Ticks;
+//! #     fn start<T>(&mut self, _: T) where T: Into<Ticks> {}
+//! #     fn wait(&mut self) -> nb::Result<(), void::Void> { Ok(()) }
+//! # }
+//! ```
+
+use core::convert::TryFrom;
+
+/// Hertz
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Hertz(pub u32);
+
+/// Kilohertz
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct KiloHertz(pub u32);
+
+/// Megahertz
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct MegaHertz(pub u32);
+
+/// Nanoseconds
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Nanoseconds(pub u32);
+
+/// Microseconds
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Microseconds(pub u32);
+
+/// Milliseconds
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Milliseconds(pub u32);
+
+/// Seconds
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Seconds(pub u32);
+
+/// The value doesn't fit in the target unit
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Overflow;
+
+macro_rules! checked_conversions {
+    ($($from:ident => $to:ident: $factor:expr,)+) => {
+        $(
+            impl TryFrom<$from> for $to {
+                type Error = Overflow;
+
+                fn try_from(value: $from) -> Result<$to, Overflow> {
+                    value.0.checked_mul($factor).map($to).ok_or(Overflow)
+                }
+            }
+        )+
+    }
+}
+
+checked_conversions! {
+    KiloHertz => Hertz: 1_000,
+    MegaHertz => Hertz: 1_000_000,
+    MegaHertz => KiloHertz: 1_000,
+    Microseconds => Nanoseconds: 1_000,
+    Milliseconds => Nanoseconds: 1_000_000,
+    Milliseconds => Microseconds: 1_000,
+    Seconds => Nanoseconds: 1_000_000_000,
+    Seconds => Microseconds: 1_000_000,
+    Seconds => Milliseconds: 1_000,
+}
+
+/// Extension trait that adds convenience methods to the `u32` type
+pub trait U32Ext {
+    /// Wraps in `Hertz`
+    fn hz(self) -> Hertz;
+
+    /// Wraps in `KiloHertz`
+    fn khz(self) -> KiloHertz;
+
+    /// Wraps in `MegaHertz`
+    fn mhz(self) -> MegaHertz;
+
+    /// Wraps in `Nanoseconds`
+    fn ns(self) -> Nanoseconds;
+
+    /// Wraps in `Microseconds`
+    fn us(self) -> Microseconds;
+
+    /// Wraps in `Milliseconds`
+    fn ms(self) -> Milliseconds;
+
+    /// Wraps in `Seconds`
+    fn s(self) -> Seconds;
+}
+
+impl U32Ext for u32 {
+    fn hz(self) -> Hertz {
+        Hertz(self)
+    }
+
+    fn khz(self) -> KiloHertz {
+        KiloHertz(self)
+    }
+
+    fn mhz(self) -> MegaHertz {
+        MegaHertz(self)
+    }
+
+    fn ns(self) -> Nanoseconds {
+        Nanoseconds(self)
+    }
+
+    fn us(self) -> Microseconds {
+        Microseconds(self)
+    }
+
+    fn ms(self) -> Milliseconds {
+        Milliseconds(self)
+    }
+
+    fn s(self) -> Seconds {
+        Seconds(self)
+    }
+}
+
+/// A `Time` type that can be created from any of the standard duration units
+///
+/// This trait is implemented for every type that implements the `From` conversions; use it to
+/// shorten `where` clauses.
+pub trait DurationUnits:
+    From<Nanoseconds> + From<Microseconds> + From<Milliseconds> + From<Seconds>
+{
+}
+
+impl<T> DurationUnits for T where
+    T: From<Nanoseconds> + From<Microseconds> + From<Milliseconds> + From<Seconds>
+{
+}
+
+/// A `Time` type that can be created from any of the standard frequency units
+///
+/// This trait is implemented for every type that implements the `From` conversions; use it to
+/// shorten `where` clauses.
+pub trait FrequencyUnits: From<Hertz> + From<KiloHertz> + From<MegaHertz> {}
+
+impl<T> FrequencyUnits for T where T: From<Hertz> + From<KiloHertz> + From<MegaHertz> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checked_conversions() {
+        assert_eq!(Hertz::try_from(72.mhz()), Ok(Hertz(72_000_000)));
+        assert_eq!(KiloHertz::try_from(4.mhz()), Ok(KiloHertz(4_000)));
+        assert_eq!(Hertz::try_from(5.mhz()), Ok(5_000_000.hz()));
+        assert_eq!(Hertz::try_from(4_295.mhz()), Err(Overflow));
+
+        assert_eq!(Microseconds::try_from(2.ms()), Ok(Microseconds(2_000)));
+        assert_eq!(Nanoseconds::try_from(4.s()), Ok(Nanoseconds(4_000_000_000)));
+        assert_eq!(Nanoseconds::try_from(5.s()), Err(Overflow));
+        assert_eq!(Milliseconds::try_from(4_294_968.s()), Err(Overflow));
+    }
+}
@@ -0,0 +1,480 @@
+//! Timers
+
+#[cfg(feature = "unproven")]
+use core::{fmt, mem, ops};
+
+use nb;
+use void::Void;
+
+#[cfg(feature = "unproven")]
+pub mod service;
+#[cfg(feature = "unproven")]
+pub mod soft;
+#[cfg(feature = "unproven")]
+pub mod timeout;
+
+/// A count down timer
+///
+/// # Contract
+///
+/// - `self.start(count); block!(self.wait());` MUST block for AT LEAST the time specified by
+/// `count`.
+///
+/// *Note* that the implementer doesn't necessarily have to be a *downcounting* timer; it could also
+/// be an *upcounting* timer as long as the above contract is upheld.
+///
+/// # Examples
+///
+/// You can use this timer to create delays
+///
+/// ```
+/// extern crate embedded_hal as hal;
+/// #[macro_use(block)]
+/// extern crate nb;
+///
+/// use hal::prelude::*;
+///
+/// fn main() {
+///     let mut led: Led = {
+///         // ..
+/// #       Led
+///     };
+///     let mut timer: Timer6 = {
+///         // ..
+/// #       Timer6
+///     };
+///
+///     Led.on();
+///     timer.start(1.s());
+///     block!(timer.wait()); // blocks for 1 second
+///     Led.off();
+/// }
+///
+/// # extern crate void;
+/// # use void::Void;
+/// # struct Seconds(u32);
+/// # trait U32Ext { fn s(self) -> Seconds; }
+/// # impl U32Ext for u32 { fn s(self) -> Seconds { Seconds(self) } }
+/// # struct Led;
+/// # impl Led {
+/// #     pub fn off(&mut self) {}
+/// #     pub fn on(&mut self) {}
+/// # }
+/// # struct Timer6;
+/// # impl hal::timer::CountDown for Timer6 {
+/// #     type Time = Seconds;
+/// #     fn start<T>(&mut self, _: T) where T: Into<Seconds> {}
+/// #     fn wait(&mut self) -> ::nb::Result<(), Void> { Ok(()) }
+/// # }
+/// ```
+pub trait CountDown {
+    /// The unit of time used by this timer
+    type Time;
+
+    /// Starts a new count down
+    fn start<T>(&mut self, count: T)
+    where
+        T: Into<Self::Time>;
+
+    /// Non-blockingly "waits" until the count down finishes
+    ///
+    /// # Contract
+    ///
+    /// - If `Self: Periodic`, the timer will start a new count down right after the last one
+    /// finishes.
+    /// - Otherwise the behavior of calling `wait` after the last call returned `Ok` is UNSPECIFIED.
+    /// Implementers are suggested to panic on this scenario to signal a programmer error.
+    fn wait(&mut self) -> nb::Result<(), Void>;
+}
+
+/// Marker trait that indicates that a timer is periodic
+pub trait Periodic {}
+
+/// Trait for cancelable countdowns.
+pub trait Cancel : CountDown {
+    /// Error returned when a countdown can't be canceled.
+    type Error;
+
+    /// Tries to cancel this countdown.
+    ///
+    /// # Errors
+    ///
+    /// An error will be returned if the countdown has already been canceled or was never started.
+    /// An error is also returned if the countdown is not `Periodic` and has already expired.
+    fn cancel(&mut self) -> Result<(), Self::Error>;
+}
+
+/// Count down that can report the time left until it finishes
+///
+/// *This trait is available if embedded-hal is built with the `"unproven"` feature.*
+#[cfg(feature = "unproven")]
+pub trait Remaining: CountDown {
+    /// Returns the time left until the current count down finishes
+    ///
+    /// Returns `None` if no count down is in progress, i.e. the timer was never started, was
+    /// canceled or, if it's not `Periodic`, has already finished.
+    fn remaining(&mut self) -> Option<Self::Time>;
+}
+
+/// Count down that can be paused and resumed
+///
+/// *This trait is available if embedded-hal is built with the `"unproven"` feature.*
+#[cfg(feature = "unproven")]
+pub trait Pause: CountDown {
+    /// Error returned when the count down can't be paused or resumed
+    type Error;
+
+    /// Freezes the count down; the time left is kept
+    ///
+    /// # Errors
+    ///
+    /// An error will be returned if the count down is not in progress or is already paused.
+    fn pause(&mut self) -> Result<(), Self::Error>;
+
+    /// Continues a paused count down from where it was paused
+    ///
+    /// # Errors
+    ///
+    /// An error will be returned if the count down is not paused.
+    fn resume(&mut self) -> Result<(), Self::Error>;
+}
+
+/// Periodic timer whose period can be changed while it's running
+///
+/// *This trait is available if embedded-hal is built with the `"unproven"` feature.*
+#[cfg(feature = "unproven")]
+pub trait SetPeriod: CountDown + Periodic {
+    /// Changes the period of the timer
+    ///
+    /// # Contract
+    ///
+    /// - The count down in progress MUST NOT be restarted or shortened; the new period takes effect
+    ///   from the next count down on. This avoids glitches in e.g. control loops that adjust their
+    ///   rate on the fly.
+    fn set_period<T>(&mut self, period: T)
+    where
+        T: Into<Self::Time>;
+}
+
+/// Unsigned integer type that holds a number of timer ticks
+///
+/// *This trait is available if embedded-hal is built with the `"unproven"` feature.*
+#[cfg(feature = "unproven")]
+pub trait Ticks: Copy + Eq + Ord + fmt::Debug {
+    /// Number of bits of the type; the tick count wraps around every `2^BITS` ticks
+    const BITS: u32;
+
+    /// Zero ticks
+    const ZERO: Self;
+
+    /// Wrapping (modular) addition
+    fn wrapping_add(self, rhs: Self) -> Self;
+
+    /// Wrapping (modular) subtraction
+    fn wrapping_sub(self, rhs: Self) -> Self;
+
+    /// Converts the tick count into an `u64`
+    fn into_u64(self) -> u64;
+
+    /// Converts the lowest `BITS` bits of `ticks`, discarding the rest
+    fn from_u64_truncating(ticks: u64) -> Self;
+}
+
+macro_rules! ticks {
+    ($($ty:ident,)+) => {
+        $(
+            #[cfg(feature = "unproven")]
+            impl Ticks for $ty {
+                const BITS: u32 = 8 * mem::size_of::<$ty>() as u32;
+
+                const ZERO: Self = 0;
+
+                fn wrapping_add(self, rhs: Self) -> Self {
+                    $ty::wrapping_add(self, rhs)
+                }
+
+                fn wrapping_sub(self, rhs: Self) -> Self {
+                    $ty::wrapping_sub(self, rhs)
+                }
+
+                fn into_u64(self) -> u64 {
+                    u64::from(self)
+                }
+
+                fn from_u64_truncating(ticks: u64) -> Self {
+                    ticks as $ty
+                }
+            }
+        )+
+    }
+}
+
+ticks!(u16, u32, u64,);
+
+/// A point in time, as the value of a wrapping tick counter
+///
+/// Instants are compared modulo the counter range: `a.is_before(b)` holds if `b` is less than
+/// half the counter range ahead of `a`. This keeps comparisons correct when the counter wraps
+/// around, as long as the instants being compared are less than `2^(BITS - 1)` ticks apart.
+///
+/// *This struct is available if embedded-hal is built with the `"unproven"` feature.*
+#[cfg(feature = "unproven")]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct Instant<T> {
+    ticks: T,
+}
+
+#[cfg(feature = "unproven")]
+impl<T> Instant<T>
+where
+    T: Ticks,
+{
+    /// Creates an instant from the value of a tick counter
+    pub fn new(ticks: T) -> Self {
+        Instant { ticks }
+    }
+
+    /// Returns the value of the tick counter at this instant
+    pub fn ticks(&self) -> T {
+        self.ticks
+    }
+
+    /// Returns the number of ticks from `earlier` to `self`
+    ///
+    /// If `earlier` is actually later than `self` the result wraps around.
+    pub fn duration_since(&self, earlier: Instant<T>) -> T {
+        self.ticks.wrapping_sub(earlier.ticks)
+    }
+
+    /// Returns `true` if `self` comes strictly before `other`
+    pub fn is_before(&self, other: Instant<T>) -> bool {
+        let ahead = other.ticks.wrapping_sub(self.ticks).into_u64();
+
+        ahead != 0 && ahead < 1 << (T::BITS - 1)
+    }
+
+    /// Returns `true` if `self` comes strictly after `other`
+    pub fn is_after(&self, other: Instant<T>) -> bool {
+        other.is_before(*self)
+    }
+}
+
+#[cfg(feature = "unproven")]
+impl<T> ops::Add<T> for Instant<T>
+where
+    T: Ticks,
+{
+    type Output = Instant<T>;
+
+    /// Returns the instant `ticks` after `self`, wrapping around
+    fn add(self, ticks: T) -> Instant<T> {
+        Instant::new(self.ticks.wrapping_add(ticks))
+    }
+}
+
+#[cfg(feature = "unproven")]
+impl<T> ops::Sub for Instant<T>
+where
+    T: Ticks,
+{
+    type Output = T;
+
+    /// Same as [`duration_since`](#method.duration_since)
+    fn sub(self, earlier: Instant<T>) -> T {
+        self.duration_since(earlier)
+    }
+}
+
+/// A clock that never goes backwards
+///
+/// The clock counts ticks at a fixed frequency and wraps around once its counter overflows. See
+/// [`Instant`](struct.Instant.html) for how to compare readings across wraparounds, and
+/// [`Extend64`](struct.Extend64.html) for a clock that practically never wraps around.
+///
+/// *This trait is available if embedded-hal is built with the `"unproven"` feature.*
+///
+/// # Examples
+///
+/// Measuring how long an operation takes
+///
+/// ```
+/// extern crate embedded_hal as hal;
+///
+/// use hal::timer::Monotonic;
+///
+/// fn main() {
+///     let mut clock: Clock = {
+///         // ..
+/// #       Clock(0)
+///     };
+///
+///     let start = clock.now();
+///     // omitted: do something
+///     let ticks = clock.elapsed(start);
+///
+///     let us = u64::from(ticks) * 1_000_000 / u64::from(clock.frequency());
+///     println!("took {} us", us);
+/// }
+///
+/// # struct Clock(u32);
+/// # impl Monotonic for Clock {
+/// #     type Ticks = u32;
+/// #     fn frequency(&self) -> u32 { 1_000_000 }
+/// #     fn now(&mut self) -> hal::timer::Instant<u32> {
+/// #         self.0 += 42;
+/// #         hal::timer::Instant::new(self.0)
+/// #     }
+/// # }
+/// ```
+#[cfg(feature = "unproven")]
+pub trait Monotonic {
+    /// Type of the tick counter
+    type Ticks: Ticks;
+
+    /// Returns the frequency of the clock, in ticks per second
+    fn frequency(&self) -> u32;
+
+    /// Returns the current instant
+    ///
+    /// # Contract
+    ///
+    /// - Consecutive readings MUST NOT go backwards, other than by the counter wrapping around.
+    fn now(&mut self) -> Instant<Self::Ticks>;
+
+    /// Returns the number of ticks since `earlier`
+    fn elapsed(&mut self, earlier: Instant<Self::Ticks>) -> Self::Ticks {
+        self.now().duration_since(earlier)
+    }
+}
+
+/// Extends the tick counter of a clock to 64 bits
+///
+/// The wraparounds of the wrapped clock are counted in software, which only works if its `now`
+/// method is called more often than every `2^BITS` ticks; e.g. every 65 ms for a 16-bit counter
+/// running at 1 MHz. Call it at least once per half wraparound period to leave a margin for
+/// latency.
+///
+/// As `now` takes `&mut self`, an `Extend64` lives in a single context. If it's also read from a
+/// periodic interrupt handler, to keep it up to date, it has to be shared between the handler and
+/// the application behind a mutex, e.g. one that disables interrupts while it's locked.
+///
+/// *This struct is available if embedded-hal is built with the `"unproven"` feature.*
+#[cfg(feature = "unproven")]
+pub struct Extend64<C> {
+    clock: C,
+    // ticks that have passed until the last wraparound
+    offset: u64,
+    last: u64,
+}
+
+#[cfg(feature = "unproven")]
+impl<C> Extend64<C>
+where
+    C: Monotonic,
+{
+    /// Wraps `clock`
+    ///
+    /// The current value of `clock` is kept, i.e. the extended clock starts at the same instant.
+    pub fn new(mut clock: C) -> Self {
+        let last = clock.now().ticks().into_u64();
+
+        Extend64 {
+            clock,
+            offset: 0,
+            last,
+        }
+    }
+
+    /// Releases the wrapped clock
+    pub fn free(self) -> C {
+        self.clock
+    }
+}
+
+#[cfg(feature = "unproven")]
+impl<C> Monotonic for Extend64<C>
+where
+    C: Monotonic,
+{
+    type Ticks = u64;
+
+    fn frequency(&self) -> u32 {
+        self.clock.frequency()
+    }
+
+    fn now(&mut self) -> Instant<u64> {
+        let ticks = self.clock.now().ticks().into_u64();
+
+        if ticks < self.last {
+            // zero for a 64-bit counter, which is never going to wrap around anyway
+            let period = 1u64.checked_shl(C::Ticks::BITS).unwrap_or(0);
+            self.offset = self.offset.wrapping_add(period);
+        }
+        self.last = ticks;
+
+        Instant::new(self.offset + ticks)
+    }
+}
+
+#[cfg(all(test, feature = "unproven"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn instant_wraparound() {
+        let before = Instant::new(0xfff0_u16);
+        let after = before + 0x20;
+
+        assert_eq!(after.ticks(), 0x10);
+        assert!(before.is_before(after));
+        assert!(after.is_after(before));
+        assert!(!after.is_before(before));
+        assert!(!before.is_before(before));
+        assert_eq!(after - before, 0x20);
+        assert_eq!(after.duration_since(before), 0x20);
+
+        // more than half the range apart
+        assert!(after.is_before(before + 0x8000));
+        assert!(!before.is_before(before + 0x8000));
+    }
+
+    /// Clock that advances by `step` ticks on every reading
+    struct Clock {
+        ticks: u16,
+        step: u16,
+    }
+
+    impl Monotonic for Clock {
+        type Ticks = u16;
+
+        fn frequency(&self) -> u32 {
+            1_000
+        }
+
+        fn now(&mut self) -> Instant<u16> {
+            self.ticks = self.ticks.wrapping_add(self.step);
+            Instant::new(self.ticks)
+        }
+    }
+
+    #[test]
+    fn extend64() {
+        let mut clock = Extend64::new(Clock {
+            ticks: 0xff00,
+            step: 0x6000,
+        });
+        assert_eq!(clock.frequency(), 1_000);
+
+        let start = clock.now();
+        assert_eq!(start.ticks(), 0xbf00);
+        let mut last = start;
+        for _ in 0..10 {
+            let now = clock.now();
+            assert_eq!(now - last, 0x6000);
+            last = now;
+        }
+
+        assert_eq!(clock.elapsed(start), 11 * 0x6000);
+        assert_eq!(clock.free().ticks, 0xbf00_u16.wrapping_add(0x6000_u16.wrapping_mul(11)));
+    }
+}
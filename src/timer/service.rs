@@ -0,0 +1,383 @@
+//! Software timers multiplexed on a single hardware timer
+//!
+//! *This module is available if embedded-hal is built with the `"unproven"` feature.*
+
+use nb;
+use void::Void;
+
+use super::{CountDown, Instant, Periodic};
+
+/// Handle to a virtual timer of a [`TimerService`](struct.TimerService.html)
+///
+/// Handles become stale once their timer is canceled or, for one-shot timers, has expired; stale
+/// handles are never confused with timers started later on in the same slot, nor with timers of
+/// another service.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct Handle {
+    // address of the slots of the service that issued the handle
+    service: usize,
+    index: usize,
+    generation: u32,
+}
+
+/// Storage for one virtual timer of a [`TimerService`](struct.TimerService.html)
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Slot {
+    generation: u32,
+    // `None` if the slot is free
+    deadline: Option<Instant<u32>>,
+    // zero for one-shot timers
+    period: u32,
+}
+
+/// Fixed-capacity set of virtual timers driven by a periodic `CountDown`
+///
+/// Every time the hardware timer fires the service advances by one *tick*. Virtual timer
+/// durations and periods are expressed in ticks and must be shorter than `2^31` ticks.
+///
+/// The service runs at most one virtual timer per caller-provided [`Slot`](struct.Slot.html).
+///
+/// The hardware timer must already be running with the desired tick period when it's handed
+/// to the service.
+///
+/// # Examples
+///
+/// ```
+/// extern crate embedded_hal as hal;
+/// #[macro_use(block)]
+/// extern crate nb;
+///
+/// use hal::timer::service::{Slot, TimerService};
+///
+/// fn main() {
+///     // started with a period of 1 ms
+///     let timer: Timer6 = {
+///         // ..
+/// #       Timer6(false)
+///     };
+///
+///     let mut slots = [Slot::default(); 8];
+///     let mut timers = TimerService::new(timer, &mut slots);
+///     let blink = timers.start_periodic(500).unwrap();
+///     let feed = timers.start_periodic(100).unwrap();
+///     let retransmit = timers.start_one_shot(250).unwrap();
+///
+///     for _ in 0..10 {
+///         let expired = block!(timers.poll()).unwrap();
+///         if expired == blink {
+///             // toggle the LED
+///         } else if expired == feed {
+///             // feed the watchdog
+///         } else if expired == retransmit {
+///             // resend the last packet
+///         }
+///     }
+/// }
+///
+/// # extern crate void;
+/// # struct Timer6(bool);
+/// # impl hal::timer::CountDown for Timer6 {
+/// #     type Time = u32;
+/// #     fn start<T>(&mut self, _: T) where T: Into<u32> {}
+/// #     fn wait(&mut self) -> nb::Result<(), void::Void> {
+/// #         self.0 = !self.0;
+/// #         if self.0 { Ok(()) } else { Err(nb::Error::WouldBlock) }
+/// #     }
+/// # }
+/// # impl hal::timer::Periodic for Timer6 {}
+/// ```
+pub struct TimerService<'a, T> {
+    timer: T,
+    now: Instant<u32>,
+    slots: &'a mut [Slot],
+    // slot where `poll` starts looking for expired timers
+    next: usize,
+}
+
+impl<'a, T> TimerService<'a, T>
+where
+    T: CountDown + Periodic,
+{
+    /// Creates a service with no virtual timers running that keeps its timers in `slots`
+    pub fn new(timer: T, slots: &'a mut [Slot]) -> Self {
+        // stale handles to timers left running in `slots` by a previous service
+        for slot in slots.iter_mut() {
+            if slot.deadline.take().is_some() {
+                slot.generation = slot.generation.wrapping_add(1);
+            }
+        }
+
+        TimerService {
+            timer,
+            now: Instant::new(0),
+            slots,
+            next: 0,
+        }
+    }
+
+    /// Releases the hardware timer
+    pub fn free(self) -> T {
+        self.timer
+    }
+
+    /// Returns the number of ticks since the service was created, wrapping around
+    pub fn now(&self) -> Instant<u32> {
+        self.now
+    }
+
+    /// Starts a timer that expires once, `ticks` ticks from now
+    ///
+    /// Returns `None` if all the slots are in use.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `ticks` is `2^31` or more.
+    pub fn start_one_shot(&mut self, ticks: u32) -> Option<Handle> {
+        self.start(ticks, 0)
+    }
+
+    /// Starts a timer that expires every `period` ticks
+    ///
+    /// Returns `None` if all the slots are in use.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `period` is zero, or `2^31` or more.
+    pub fn start_periodic(&mut self, period: u32) -> Option<Handle> {
+        assert!(period > 0);
+
+        self.start(period, period)
+    }
+
+    /// Stops a timer
+    ///
+    /// Returns `false` if the timer was no longer running, or if `handle` doesn't belong to this
+    /// service.
+    pub fn cancel(&mut self, handle: Handle) -> bool {
+        if self.is_running(handle) {
+            self.release(handle.index);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns `true` if the timer is still running
+    ///
+    /// One-shot timers stop running when `poll` reports them as expired. Returns `false` if
+    /// `handle` doesn't belong to this service.
+    pub fn is_running(&self, handle: Handle) -> bool {
+        if handle.service != self.id() {
+            return false;
+        }
+
+        match self.slots.get(handle.index) {
+            Some(slot) => slot.generation == handle.generation && slot.deadline.is_some(),
+            None => false,
+        }
+    }
+
+    /// Advances time and returns an expired timer
+    ///
+    /// This method must be called at least once per tick. Each call reports at most one expired
+    /// timer, so keep calling it until it returns `WouldBlock` to collect all of them.
+    ///
+    /// A periodic timer that falls behind by several periods is reported once per missed period.
+    /// Expired timers are reported round robin, so a timer that falls behind doesn't keep the
+    /// others from being reported.
+    pub fn poll(&mut self) -> nb::Result<Handle, Void> {
+        loop {
+            match self.timer.wait() {
+                Ok(()) => self.now = self.now + 1,
+                Err(nb::Error::WouldBlock) => break,
+                Err(nb::Error::Other(e)) => match e {},
+            }
+        }
+
+        let now = self.now;
+        let len = self.slots.len();
+        let next = self.next;
+        let index = (next..len)
+            .chain(0..next)
+            .find(|&index| match self.slots[index].deadline {
+                Some(deadline) => !now.is_before(deadline),
+                None => false,
+            })
+            .ok_or(nb::Error::WouldBlock)?;
+        self.next = (index + 1) % len;
+
+        let handle = Handle {
+            service: self.id(),
+            index,
+            generation: self.slots[index].generation,
+        };
+        let slot = &mut self.slots[index];
+        if slot.period == 0 {
+            self.release(index);
+        } else {
+            slot.deadline = slot.deadline.map(|deadline| deadline + slot.period);
+        }
+
+        Ok(handle)
+    }
+
+    fn start(&mut self, ticks: u32, period: u32) -> Option<Handle> {
+        assert!(ticks < 1 << 31);
+
+        let now = self.now;
+        let service = self.id();
+        let index = self.slots.iter().position(|slot| slot.deadline.is_none())?;
+        let slot = &mut self.slots[index];
+        slot.deadline = Some(now + ticks);
+        slot.period = period;
+
+        Some(Handle {
+            service,
+            index,
+            generation: slot.generation,
+        })
+    }
+
+    fn id(&self) -> usize {
+        self.slots.as_ptr() as usize
+    }
+
+    fn release(&mut self, index: usize) {
+        let slot = &mut self.slots[index];
+        slot.deadline = None;
+        slot.generation = slot.generation.wrapping_add(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+
+    use super::*;
+
+    /// Timer that fires whenever the test says so
+    struct Timer<'a> {
+        pending: &'a Cell<u32>,
+    }
+
+    impl<'a> CountDown for Timer<'a> {
+        type Time = u32;
+
+        fn start<C>(&mut self, _: C)
+        where
+            C: Into<u32>,
+        {
+        }
+
+        fn wait(&mut self) -> nb::Result<(), Void> {
+            match self.pending.get() {
+                0 => Err(nb::Error::WouldBlock),
+                n => {
+                    self.pending.set(n - 1);
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    impl<'a> Periodic for Timer<'a> {}
+
+    #[test]
+    fn one_shot_and_periodic() {
+        let pending = Cell::new(0);
+        let mut slots = [Slot::default(); 2];
+        let mut timers = TimerService::new(Timer { pending: &pending }, &mut slots);
+
+        let blink = timers.start_periodic(3).unwrap();
+        let once = timers.start_one_shot(4).unwrap();
+        assert_eq!(timers.start_one_shot(1), None);
+
+        pending.set(2);
+        assert_eq!(timers.poll(), Err(nb::Error::WouldBlock));
+        pending.set(1);
+        assert_eq!(timers.poll(), Ok(blink));
+        assert_eq!(timers.poll(), Err(nb::Error::WouldBlock));
+        pending.set(1);
+        assert_eq!(timers.poll(), Ok(once));
+        assert!(!timers.is_running(once));
+        assert!(timers.is_running(blink));
+
+        // the periodic timer catches up on missed periods
+        pending.set(5);
+        assert_eq!(timers.poll(), Ok(blink));
+        assert_eq!(timers.poll(), Ok(blink));
+        assert_eq!(timers.poll(), Err(nb::Error::WouldBlock));
+        assert_eq!(timers.now(), Instant::new(9));
+    }
+
+    #[test]
+    fn stale_handles() {
+        let pending = Cell::new(0);
+        let mut slots = [Slot::default(); 1];
+        let mut timers = TimerService::new(Timer { pending: &pending }, &mut slots);
+
+        let first = timers.start_one_shot(1).unwrap();
+        assert!(timers.cancel(first));
+        assert!(!timers.cancel(first));
+
+        let second = timers.start_one_shot(1).unwrap();
+        assert_ne!(first, second);
+        assert!(!timers.cancel(first));
+        assert!(timers.is_running(second));
+
+        pending.set(1);
+        assert_eq!(timers.poll(), Ok(second));
+        assert_eq!(timers.poll(), Err(nb::Error::WouldBlock));
+        assert!(!timers.cancel(second));
+    }
+
+    #[test]
+    fn foreign_handles() {
+        let pending = Cell::new(0);
+        let mut a_slots = [Slot::default(); 1];
+        let mut a = TimerService::new(Timer { pending: &pending }, &mut a_slots);
+        let mut b_slots = [Slot::default(); 1];
+        let mut b = TimerService::new(Timer { pending: &pending }, &mut b_slots);
+
+        // same slot index and generation in both services
+        let foreign = a.start_one_shot(1).unwrap();
+        let local = b.start_one_shot(1).unwrap();
+        assert!(!b.is_running(foreign));
+        assert!(!b.cancel(foreign));
+        assert!(b.is_running(local));
+    }
+
+    #[test]
+    fn reused_slots() {
+        let pending = Cell::new(0);
+        let mut slots = [Slot::default(); 1];
+
+        let old = TimerService::new(Timer { pending: &pending }, &mut slots)
+            .start_one_shot(1)
+            .unwrap();
+        let mut timers = TimerService::new(Timer { pending: &pending }, &mut slots);
+        let new = timers.start_one_shot(1).unwrap();
+        assert!(!timers.cancel(old));
+        assert!(timers.is_running(new));
+    }
+
+    #[test]
+    fn round_robin() {
+        let pending = Cell::new(0);
+        let mut slots = [Slot::default(); 2];
+        let mut timers = TimerService::new(Timer { pending: &pending }, &mut slots);
+
+        let fast = timers.start_periodic(1).unwrap();
+        let slow = timers.start_periodic(2).unwrap();
+
+        // `fast` has fallen behind by several periods but doesn't starve `slow`
+        pending.set(4);
+        assert_eq!(timers.poll(), Ok(fast));
+        assert_eq!(timers.poll(), Ok(slow));
+        assert_eq!(timers.poll(), Ok(fast));
+        assert_eq!(timers.poll(), Ok(slow));
+        assert_eq!(timers.poll(), Ok(fast));
+        assert_eq!(timers.poll(), Ok(fast));
+        assert_eq!(timers.poll(), Err(nb::Error::WouldBlock));
+    }
+}
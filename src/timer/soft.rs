@@ -0,0 +1,291 @@
+//! Software timer on top of a monotonic clock
+//!
+//! *This module is available if embedded-hal is built with the `"unproven"` feature.*
+
+use nb;
+use void::Void;
+
+use super::{Cancel, CountDown, Instant, Monotonic, Pause, Periodic, Remaining, SetPeriod, Ticks};
+
+/// Error of an operation that doesn't apply to the current state of a [`SoftTimer`]
+///
+/// [`SoftTimer`]: struct.SoftTimer.html
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Error {
+    /// The timer was never started or was canceled
+    Idle,
+    /// The timer is already paused
+    Paused,
+    /// The timer is not paused
+    NotPaused,
+}
+
+#[derive(Clone, Copy)]
+enum State<T> {
+    Idle,
+    Running { deadline: Instant<T> },
+    Paused { remaining: T },
+}
+
+/// Periodic count down timer that reads a `Monotonic` clock
+///
+/// This is a reference implementation of the `Cancel`, `Remaining`, `Pause` and `SetPeriod`
+/// traits. Its `Time` unit is ticks of the clock; periods must be shorter than `2^(BITS - 1)`
+/// ticks.
+///
+/// The clock is only read from the timer methods, so a finished count down goes unnoticed until
+/// `wait` is called. A periodic count down is still restarted from its deadline, not from the
+/// time `wait` was called, so the timer doesn't drift.
+///
+/// # Examples
+///
+/// ```
+/// extern crate embedded_hal as hal;
+/// #[macro_use(block)]
+/// extern crate nb;
+///
+/// use hal::prelude::*;
+/// use hal::timer::soft::SoftTimer;
+///
+/// fn main() {
+///     // 1 MHz clock
+///     let clock: Clock = {
+///         // ..
+/// #       Clock(0)
+///     };
+///
+///     let mut timer = SoftTimer::new(clock);
+///     timer.start(1_000_u32);
+///
+///     for rate in 1..10_u32 {
+///         block!(timer.wait()).unwrap();
+///         // omitted: run the control loop
+///         timer.set_period(1_000 / rate);
+///     }
+/// }
+///
+/// # struct Clock(u32);
+/// # impl hal::timer::Monotonic for Clock {
+/// #     type Ticks = u32;
+/// #     fn frequency(&self) -> u32 { 1_000_000 }
+/// #     fn now(&mut self) -> hal::timer::Instant<u32> {
+/// #         self.0 += 10;
+/// #         hal::timer::Instant::new(self.0)
+/// #     }
+/// # }
+/// ```
+pub struct SoftTimer<C>
+where
+    C: Monotonic,
+{
+    clock: C,
+    period: C::Ticks,
+    state: State<C::Ticks>,
+}
+
+impl<C> SoftTimer<C>
+where
+    C: Monotonic,
+{
+    /// Creates a timer that is not running yet
+    pub fn new(clock: C) -> Self {
+        SoftTimer {
+            clock,
+            period: C::Ticks::ZERO,
+            state: State::Idle,
+        }
+    }
+
+    /// Releases the clock
+    pub fn free(self) -> C {
+        self.clock
+    }
+}
+
+impl<C> CountDown for SoftTimer<C>
+where
+    C: Monotonic,
+{
+    type Time = C::Ticks;
+
+    fn start<T>(&mut self, count: T)
+    where
+        T: Into<C::Ticks>,
+    {
+        self.period = count.into();
+        self.state = State::Running {
+            deadline: self.clock.now() + self.period,
+        };
+    }
+
+    fn wait(&mut self) -> nb::Result<(), Void> {
+        match self.state {
+            State::Running { deadline } if !self.clock.now().is_before(deadline) => {
+                self.state = State::Running {
+                    deadline: deadline + self.period,
+                };
+                Ok(())
+            }
+            _ => Err(nb::Error::WouldBlock),
+        }
+    }
+}
+
+impl<C> Periodic for SoftTimer<C> where C: Monotonic {}
+
+impl<C> Cancel for SoftTimer<C>
+where
+    C: Monotonic,
+{
+    type Error = Error;
+
+    fn cancel(&mut self) -> Result<(), Error> {
+        match self.state {
+            State::Idle => Err(Error::Idle),
+            _ => {
+                self.state = State::Idle;
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<C> Remaining for SoftTimer<C>
+where
+    C: Monotonic,
+{
+    fn remaining(&mut self) -> Option<C::Ticks> {
+        match self.state {
+            State::Idle => None,
+            State::Running { deadline } => {
+                let now = self.clock.now();
+                if now.is_before(deadline) {
+                    Some(deadline - now)
+                } else {
+                    Some(C::Ticks::ZERO)
+                }
+            }
+            State::Paused { remaining } => Some(remaining),
+        }
+    }
+}
+
+impl<C> Pause for SoftTimer<C>
+where
+    C: Monotonic,
+{
+    type Error = Error;
+
+    fn pause(&mut self) -> Result<(), Error> {
+        match self.state {
+            State::Idle => Err(Error::Idle),
+            State::Paused { .. } => Err(Error::Paused),
+            State::Running { .. } => {
+                let remaining = self.remaining().unwrap_or(C::Ticks::ZERO);
+                self.state = State::Paused { remaining };
+                Ok(())
+            }
+        }
+    }
+
+    fn resume(&mut self) -> Result<(), Error> {
+        match self.state {
+            State::Idle => Err(Error::Idle),
+            State::Running { .. } => Err(Error::NotPaused),
+            State::Paused { remaining } => {
+                self.state = State::Running {
+                    deadline: self.clock.now() + remaining,
+                };
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<C> SetPeriod for SoftTimer<C>
+where
+    C: Monotonic,
+{
+    fn set_period<T>(&mut self, period: T)
+    where
+        T: Into<C::Ticks>,
+    {
+        // the deadline of the count down in progress is left untouched
+        self.period = period.into();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+
+    use super::*;
+
+    /// Clock that only advances when the test says so
+    struct Clock<'a>(&'a Cell<u16>);
+
+    impl<'a> Monotonic for Clock<'a> {
+        type Ticks = u16;
+
+        fn frequency(&self) -> u32 {
+            1_000
+        }
+
+        fn now(&mut self) -> Instant<u16> {
+            Instant::new(self.0.get())
+        }
+    }
+
+    #[test]
+    fn periodic() {
+        let now = Cell::new(0xfff0);
+        let mut timer = SoftTimer::new(Clock(&now));
+        assert_eq!(timer.wait(), Err(nb::Error::WouldBlock));
+        assert_eq!(timer.remaining(), None);
+
+        timer.start(10_u16);
+        now.set(0xfff9);
+        assert_eq!(timer.wait(), Err(nb::Error::WouldBlock));
+        assert_eq!(timer.remaining(), Some(1));
+
+        // finished late; the next deadline is still 10 ticks after the previous one
+        now.set(0xfffc);
+        assert_eq!(timer.remaining(), Some(0));
+        assert_eq!(timer.wait(), Ok(()));
+        assert_eq!(timer.remaining(), Some(8));
+
+        // the count down in progress keeps its deadline
+        timer.set_period(20_u16);
+        now.set(0x0004);
+        assert_eq!(timer.wait(), Ok(()));
+        assert_eq!(timer.remaining(), Some(20));
+
+        assert_eq!(timer.cancel(), Ok(()));
+        assert_eq!(timer.cancel(), Err(Error::Idle));
+        assert_eq!(timer.wait(), Err(nb::Error::WouldBlock));
+    }
+
+    #[test]
+    fn pause_resume() {
+        let now = Cell::new(0);
+        let mut timer = SoftTimer::new(Clock(&now));
+        assert_eq!(timer.pause(), Err(Error::Idle));
+
+        timer.start(10_u16);
+        now.set(4);
+        assert_eq!(timer.resume(), Err(Error::NotPaused));
+        assert_eq!(timer.pause(), Ok(()));
+        assert_eq!(timer.pause(), Err(Error::Paused));
+
+        now.set(100);
+        assert_eq!(timer.wait(), Err(nb::Error::WouldBlock));
+        assert_eq!(timer.remaining(), Some(6));
+
+        assert_eq!(timer.resume(), Ok(()));
+        now.set(105);
+        assert_eq!(timer.wait(), Err(nb::Error::WouldBlock));
+        now.set(106);
+        assert_eq!(timer.wait(), Ok(()));
+        assert_eq!(timer.remaining(), Some(10));
+    }
+}
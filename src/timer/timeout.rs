@@ -0,0 +1,240 @@
+//! Blocking on `nb` operations with a timeout
+//!
+//! `block!` spins until the operation completes, which may be never if e.g. the other end of a
+//! serial link went away. The functions in this module, and the
+//! [`block_timeout!`](../../macro.block_timeout.html) macro, give up after a timeout instead.
+//!
+//! *This module is available if embedded-hal is built with the `"unproven"` feature.*
+
+use nb;
+
+use super::{CountDown, Instant, Monotonic};
+
+/// Error of an operation that was given a timeout
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TimeoutError<E> {
+    /// The operation didn't complete in time
+    Timeout,
+    /// The operation failed
+    Other(E),
+}
+
+impl<E> From<E> for TimeoutError<E> {
+    fn from(e: E) -> Self {
+        TimeoutError::Other(e)
+    }
+}
+
+/// Polls `op` until it completes or `timer` counts down `timeout`
+///
+/// `timer` is (re)started with `timeout`. `op` is always polled at least once.
+pub fn block_for<T, D, F, R, E>(timer: &mut T, timeout: D, mut op: F) -> Result<R, TimeoutError<E>>
+where
+    T: CountDown,
+    D: Into<T::Time>,
+    F: FnMut() -> nb::Result<R, E>,
+{
+    timer.start(timeout);
+
+    loop {
+        match op() {
+            Ok(r) => return Ok(r),
+            Err(nb::Error::Other(e)) => return Err(TimeoutError::Other(e)),
+            Err(nb::Error::WouldBlock) => {}
+        }
+
+        match timer.wait() {
+            Ok(()) => return Err(TimeoutError::Timeout),
+            Err(nb::Error::WouldBlock) => {}
+            Err(nb::Error::Other(e)) => match e {},
+        }
+    }
+}
+
+/// Polls `op` until it completes or `clock` reaches `deadline`
+///
+/// `op` is always polled at least once.
+pub fn block_until<C, F, R, E>(
+    clock: &mut C,
+    deadline: Instant<C::Ticks>,
+    mut op: F,
+) -> Result<R, TimeoutError<E>>
+where
+    C: Monotonic,
+    F: FnMut() -> nb::Result<R, E>,
+{
+    loop {
+        match op() {
+            Ok(r) => return Ok(r),
+            Err(nb::Error::Other(e)) => return Err(TimeoutError::Other(e)),
+            Err(nb::Error::WouldBlock) => {}
+        }
+
+        if !clock.now().is_before(deadline) {
+            return Err(TimeoutError::Timeout);
+        }
+    }
+}
+
+/// Turns the non-blocking expression `$e` into a blocking operation that gives up after
+/// `$timeout`, as measured by the `CountDown` timer `$timer`
+///
+/// This is the counterpart of `nb`'s `block!`. The expression evaluates to
+/// `Result<T, TimeoutError<E>>`, see [`block_for`](timer/timeout/fn.block_for.html).
+///
+/// *This macro is available if embedded-hal is built with the `"unproven"` feature.*
+///
+/// # Examples
+///
+/// ```
+/// #[macro_use(block_timeout)]
+/// extern crate embedded_hal as hal;
+///
+/// use hal::serial::Read;
+/// use hal::timer::timeout::TimeoutError;
+///
+/// fn main() {
+///     let mut serial: Serial1 = {
+///         // ..
+/// #       Serial1
+///     };
+///     let mut timer: Timer6 = {
+///         // ..
+/// #       Timer6
+///     };
+///
+///     match block_timeout!(timer, Ticks(1_000), serial.read()) {
+///         Ok(byte) => println!("received {}", byte),
+///         Err(TimeoutError::Timeout) => println!("no response"),
+///         Err(TimeoutError::Other(e)) => println!("serial error: {:?}", e),
+///     }
+/// }
+///
+/// # extern crate nb;
+/// # extern crate void;
+/// # struct Ticks(u32);
+/// # struct Serial1;
+/// # impl Read<u8> for Serial1 {
+/// #     type Error = ();
+/// #     fn read(&mut self) -> nb::Result<u8, ()> { Err(nb::Error::WouldBlock) }
+/// # }
+/// # struct Timer6;
+/// # impl hal::timer::CountDown for Timer6 {
+/// #     type Time = Ticks;
+/// #     fn start<T>(&mut self, _: T) where T: Into<Ticks> {}
+/// #     fn wait(&mut self) -> nb::Result<(), void::Void> { Ok(()) }
+/// # }
+/// ```
+#[macro_export]
+macro_rules! block_timeout {
+    ($timer:expr, $timeout:expr, $e:expr) => {
+        $crate::timer::timeout::block_for(&mut $timer, $timeout, || $e)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+
+    use void::Void;
+
+    use super::*;
+
+    /// Timer that expires after `ticks` polls
+    struct Timer {
+        ticks: u32,
+        left: u32,
+    }
+
+    impl CountDown for Timer {
+        type Time = u32;
+
+        fn start<C>(&mut self, count: C)
+        where
+            C: Into<u32>,
+        {
+            self.ticks = count.into();
+            self.left = self.ticks;
+        }
+
+        fn wait(&mut self) -> nb::Result<(), Void> {
+            if self.left == 0 {
+                Ok(())
+            } else {
+                self.left -= 1;
+                Err(nb::Error::WouldBlock)
+            }
+        }
+    }
+
+    /// Operation that completes after `polls` polls
+    fn op<'a>(
+        polls: &'a Cell<u32>,
+        result: Result<u8, ()>,
+    ) -> impl FnMut() -> nb::Result<u8, ()> + 'a {
+        move || match polls.get() {
+            0 => result.map_err(nb::Error::Other),
+            n => {
+                polls.set(n - 1);
+                Err(nb::Error::WouldBlock)
+            }
+        }
+    }
+
+    #[test]
+    fn count_down() {
+        let mut timer = Timer { ticks: 0, left: 0 };
+
+        let polls = Cell::new(3);
+        assert_eq!(block_for(&mut timer, 5u32, op(&polls, Ok(42))), Ok(42));
+        let polls = Cell::new(3);
+        assert_eq!(
+            block_for(&mut timer, 5u32, op(&polls, Err(()))),
+            Err(TimeoutError::Other(()))
+        );
+        let polls = Cell::new(10);
+        assert_eq!(
+            block_for(&mut timer, 5u32, op(&polls, Ok(42))),
+            Err(TimeoutError::Timeout)
+        );
+        assert_eq!(timer.ticks, 5);
+
+        // the operation is always polled at least once
+        let polls = Cell::new(0);
+        let mut op = op(&polls, Ok(1));
+        assert_eq!(block_timeout!(timer, 0u32, op()), Ok(1));
+    }
+
+    /// Clock that advances one tick per reading
+    struct Clock(u16);
+
+    impl Monotonic for Clock {
+        type Ticks = u16;
+
+        fn frequency(&self) -> u32 {
+            1_000
+        }
+
+        fn now(&mut self) -> Instant<u16> {
+            self.0 = self.0.wrapping_add(1);
+            Instant::new(self.0)
+        }
+    }
+
+    #[test]
+    fn deadline() {
+        let mut clock = Clock(0xfffe);
+        let deadline = clock.now() + 4;
+
+        let polls = Cell::new(3);
+        assert_eq!(
+            block_until(&mut clock, deadline, op(&polls, Ok(42))),
+            Ok(42)
+        );
+        let polls = Cell::new(3);
+        assert_eq!(
+            block_until(&mut clock, deadline, op(&polls, Ok(42))),
+            Err(TimeoutError::Timeout)
+        );
+    }
+}